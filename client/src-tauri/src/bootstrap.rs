@@ -0,0 +1,148 @@
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Context, Result};
+
+use crate::commands::validate_bootstrap_addr;
+
+/// 引导节点列表文件名，位于应用数据目录下
+const BOOTSTRAP_FILE: &str = "bootstrap.json";
+
+/// 用户维护的引导节点列表，以 JSON 字符串数组保存。
+///
+/// 每一项都是以 `/p2p/<PeerId>` 结尾的 multiaddr，节点启动时写入 Kademlia 路由表并拨号。
+/// 列表以规范化后的字符串保存，方便按原样删除。
+pub struct BootstrapList {
+    path: PathBuf,
+    peers: Mutex<Vec<String>>,
+}
+
+impl BootstrapList {
+    /// 打开应用数据目录下的引导列表，手动改坏的条目会被跳过。
+    /// 整个文件无法解析时改名保留，以空列表启动
+    pub fn open(dir: &Path) -> Self {
+        let path = dir.join(BOOTSTRAP_FILE);
+        let peers = load(&path).unwrap_or_else(|e| {
+            match move_aside(&path) {
+                Ok(backup) => tracing::warn!(
+                    "discarding unreadable bootstrap list: {e:#}; the file was moved to {}",
+                    backup.display()
+                ),
+                Err(move_err) => tracing::warn!(
+                    "discarding unreadable bootstrap list: {e:#}; failed to move the file aside: {move_err}"
+                ),
+            }
+            Vec::new()
+        });
+        Self {
+            path,
+            peers: Mutex::new(peers),
+        }
+    }
+
+    pub fn peers(&self) -> Vec<String> {
+        self.lock().clone()
+    }
+
+    /// 追加一个地址，已存在时不重复添加
+    pub fn add(&self, addr: &str) -> Result<Vec<String>> {
+        let addr = normalize(addr)?;
+        let mut peers = self.lock();
+        if !peers.contains(&addr) {
+            let mut updated = peers.clone();
+            updated.push(addr);
+            self.save(&updated)?;
+            *peers = updated;
+        }
+        Ok(peers.clone())
+    }
+
+    pub fn remove(&self, addr: &str) -> Result<Vec<String>> {
+        // 文件里的条目都已规范化，无法解析的输入按原样比较
+        let addr = normalize(addr).unwrap_or_else(|_| addr.trim().to_string());
+        let mut peers = self.lock();
+        if !peers.contains(&addr) {
+            return Err(anyhow!("{} is not in the bootstrap list", addr));
+        }
+        let updated: Vec<_> = peers.iter().filter(|a| **a != addr).cloned().collect();
+        self.save(&updated)?;
+        *peers = updated;
+        Ok(peers.clone())
+    }
+
+    /// 整体替换列表，任一地址无效时不做修改
+    pub fn set(&self, addrs: &[String]) -> Result<Vec<String>> {
+        let mut updated = Vec::new();
+        for addr in addrs {
+            let addr = normalize(addr)?;
+            if !updated.contains(&addr) {
+                updated.push(addr);
+            }
+        }
+        let mut peers = self.lock();
+        self.save(&updated)?;
+        *peers = updated;
+        Ok(peers.clone())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<String>> {
+        self.peers.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn save(&self, peers: &[String]) -> Result<()> {
+        let bytes = serde_json::to_vec_pretty(peers)?;
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        // 先写临时文件再改名，中途失败不会留下截断的列表
+        let tmp = self.path.with_extension("json.tmp");
+        File::create(&tmp)
+            .and_then(|mut file| {
+                file.write_all(&bytes)?;
+                file.sync_all()
+            })
+            .and_then(|()| fs::rename(&tmp, &self.path))
+            .inspect_err(|_| {
+                let _ = fs::remove_file(&tmp);
+            })
+            .with_context(|| format!("Failed to write {}", self.path.display()))
+    }
+}
+
+fn load(path: &Path) -> Result<Vec<String>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let bytes = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let entries: Vec<String> = serde_json::from_slice(&bytes)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    let mut peers = Vec::new();
+    for entry in entries {
+        match normalize(&entry) {
+            Ok(addr) if !peers.contains(&addr) => peers.push(addr),
+            Ok(_) => {}
+            Err(e) => tracing::warn!("skipping bootstrap peer: {e}"),
+        }
+    }
+    Ok(peers)
+}
+
+/// 把无法解析的列表改名为 `bootstrap.json.<时间戳>.corrupt`
+fn move_aside(path: &Path) -> std::io::Result<PathBuf> {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let backup = path.with_file_name(format!("{BOOTSTRAP_FILE}.{secs}.corrupt"));
+    fs::rename(path, &backup)?;
+    Ok(backup)
+}
+
+fn normalize(addr: &str) -> Result<String> {
+    let (_, addr) = validate_bootstrap_addr(addr).map_err(|e| anyhow!(e))?;
+    Ok(addr.to_string())
+}
@@ -0,0 +1,44 @@
+use tauri::State;
+
+use crate::node::{AddedDag, BlockInfo, FetchedDag, NodeManager, DEFAULT_NODE};
+
+/// 把文件切块组成 Merkle DAG 存入本地，并在 DHT 上发布根 CID 的 provider 记录
+#[tauri::command]
+pub async fn add_dag_file(
+    node: State<'_, NodeManager>,
+    node_id: Option<String>,
+    path: String,
+) -> Result<AddedDag, String> {
+    node.add_dag_file(node_id.as_deref().unwrap_or(DEFAULT_NODE), &path)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 按 CID 取回整个 DAG，指定 `dest_dir` 时还原为文件
+#[tauri::command]
+pub async fn fetch_dag(
+    node: State<'_, NodeManager>,
+    node_id: Option<String>,
+    cid: String,
+    dest_dir: Option<String>,
+) -> Result<FetchedDag, String> {
+    node.fetch_dag(
+        node_id.as_deref().unwrap_or(DEFAULT_NODE),
+        &cid,
+        dest_dir.as_deref(),
+    )
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn list_blocks(
+    node: State<'_, NodeManager>,
+    node_id: Option<String>,
+) -> Result<Vec<BlockInfo>, String> {
+    let blocks = node
+        .blocks(node_id.as_deref().unwrap_or(DEFAULT_NODE))
+        .await
+        .map_err(|e| e.to_string())?;
+    blocks.list().await.map_err(|e| e.to_string())
+}
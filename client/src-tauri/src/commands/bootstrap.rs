@@ -0,0 +1,34 @@
+use tauri::State;
+
+use crate::bootstrap::BootstrapList;
+
+#[tauri::command]
+pub fn get_bootstrap_peers(bootstrap: State<'_, BootstrapList>) -> Vec<String> {
+    bootstrap.peers()
+}
+
+/// 添加引导节点，地址必须以 `/p2p/<PeerId>` 结尾，下次启动节点时生效
+#[tauri::command]
+pub fn add_bootstrap_peer(
+    bootstrap: State<'_, BootstrapList>,
+    addr: String,
+) -> Result<Vec<String>, String> {
+    bootstrap.add(&addr).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn remove_bootstrap_peer(
+    bootstrap: State<'_, BootstrapList>,
+    addr: String,
+) -> Result<Vec<String>, String> {
+    bootstrap.remove(&addr).map_err(|e| e.to_string())
+}
+
+/// 整体替换引导列表，任一地址无效时保持原列表
+#[tauri::command]
+pub fn set_bootstrap_peers(
+    bootstrap: State<'_, BootstrapList>,
+    addrs: Vec<String>,
+) -> Result<Vec<String>, String> {
+    bootstrap.set(&addrs).map_err(|e| e.to_string())
+}
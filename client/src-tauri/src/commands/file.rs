@@ -0,0 +1,101 @@
+use libp2p::PeerId;
+use tauri::{ipc::Channel, State};
+
+use crate::node::{
+    DownloadedFile, FileOffer, HashAlgorithm, NodeManager, TransferEvent, DEFAULT_NODE,
+};
+
+/// 计算哈希后对外提供本地文件，缺省使用 SHA-256
+#[tauri::command]
+pub async fn offer_file(
+    node: State<'_, NodeManager>,
+    node_id: Option<String>,
+    path: String,
+    algorithm: Option<HashAlgorithm>,
+) -> Result<FileOffer, String> {
+    let files = node
+        .files(node_id.as_deref().unwrap_or(DEFAULT_NODE))
+        .await
+        .map_err(|e| e.to_string())?;
+    files
+        .offer(&path, algorithm.unwrap_or_default())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn remove_offer(
+    node: State<'_, NodeManager>,
+    node_id: Option<String>,
+    file_id: String,
+) -> Result<(), String> {
+    let files = node
+        .files(node_id.as_deref().unwrap_or(DEFAULT_NODE))
+        .await
+        .map_err(|e| e.to_string())?;
+    files.remove_offer(&file_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn list_offers(
+    node: State<'_, NodeManager>,
+    node_id: Option<String>,
+) -> Result<Vec<FileOffer>, String> {
+    let files = node
+        .files(node_id.as_deref().unwrap_or(DEFAULT_NODE))
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(files.offers())
+}
+
+/// 查询 `peer_id` 提供的文件
+#[tauri::command]
+pub async fn list_remote_files(
+    node: State<'_, NodeManager>,
+    node_id: Option<String>,
+    peer_id: PeerId,
+) -> Result<Vec<FileOffer>, String> {
+    let files = node
+        .files(node_id.as_deref().unwrap_or(DEFAULT_NODE))
+        .await
+        .map_err(|e| e.to_string())?;
+    files
+        .remote_offers(peer_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 下载文件到 `dest_dir`，进度通过 `on_progress` 推送，
+/// 之前中断的下载会从已接收的位置续传
+#[tauri::command]
+pub async fn download_file(
+    node: State<'_, NodeManager>,
+    node_id: Option<String>,
+    peer_id: PeerId,
+    file_id: String,
+    dest_dir: String,
+    on_progress: Channel<TransferEvent>,
+) -> Result<DownloadedFile, String> {
+    let files = node
+        .files(node_id.as_deref().unwrap_or(DEFAULT_NODE))
+        .await
+        .map_err(|e| e.to_string())?;
+    files
+        .download(peer_id, &file_id, &dest_dir, on_progress)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 中止下载，已接收的部分留待续传
+#[tauri::command]
+pub async fn cancel_transfer(
+    node: State<'_, NodeManager>,
+    node_id: Option<String>,
+    transfer_id: u64,
+) -> Result<(), String> {
+    let files = node
+        .files(node_id.as_deref().unwrap_or(DEFAULT_NODE))
+        .await
+        .map_err(|e| e.to_string())?;
+    files.cancel(transfer_id).map_err(|e| e.to_string())
+}
@@ -1,22 +1,18 @@
-use std::time::Duration;
-
-use anyhow::Result;
-use libp2p::{
-    futures::StreamExt, identify, noise, ping, swarm, swarm::SwarmEvent, tcp, yamux, Multiaddr,
-    PeerId, Swarm, SwarmBuilder,
-};
+use libp2p::{Multiaddr, PeerId};
 use serde::{Deserialize, Serialize};
-use tauri::{ipc::Channel, AppHandle, Manager, State};
-use tokio::{
-    select,
-    sync::{mpsc, Mutex},
+use tauri::{ipc::Channel, State};
+
+use super::node::with_bootstrap;
+use crate::{
+    bootstrap::BootstrapList,
+    keystore::Keystore,
+    node::{
+        ClosedConnection, CommandReply, ConnectionInfo, DialFailure, DiscoveredPeer, NodeCommand,
+        NodeConfig, NodeEvent, NodeManager, DEFAULT_NODE,
+    },
 };
 
-#[derive(swarm::NetworkBehaviour)]
-pub struct IdentifyBehaviour {
-    ping: ping::Behaviour,
-    identify: identify::Behaviour,
-}
+const PAGE: &str = "identify";
 
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "camelCase", tag = "command", content = "addr")]
@@ -33,13 +29,16 @@ pub enum IdentifyEvent {
         addr: String,
     },
     #[serde(rename_all = "camelCase")]
-    Connected {
-        peer_id: String,
-    },
-    #[serde(rename_all = "camelCase")]
-    Disconnected {
-        peer_id: String,
+    Dialing {
+        peer_id: Option<String>,
+        connection_id: String,
     },
+    DialFailed(DialFailure),
+    Connected(ConnectionInfo),
+    Disconnected(ClosedConnection),
+    /// 局域网内通过 mDNS 发现的节点，可直接拨号其地址
+    Discovered(DiscoveredPeer),
+    Expired(DiscoveredPeer),
     #[serde(rename_all = "camelCase")]
     Ping {
         peer_id: String,
@@ -57,107 +56,82 @@ pub enum IdentifyEvent {
     },
 }
 
-pub struct IdentifySwarmState(pub mpsc::Sender<IdentifyCommand>);
-
-fn create_identify_swarm() -> Result<Swarm<IdentifyBehaviour>> {
-    let keypair = libp2p::identity::Keypair::generate_ed25519();
-    let swarm = SwarmBuilder::with_existing_identity(keypair.clone())
-        .with_tokio()
-        .with_tcp(
-            tcp::Config::default(),
-            noise::Config::new,
-            yamux::Config::default,
-        )?
-        .with_behaviour(|keypair| IdentifyBehaviour {
-            ping: ping::Behaviour::default(),
-            identify: identify::Behaviour::new(
-                identify::Config::new("/swarmbook/0.1.0".into(), keypair.public())
-                    .with_push_listen_addr_updates(true)
-                    .with_agent_version("/swarmbook/0.1.0".into())
-                    .with_interval(Duration::from_secs(3)),
-            ),
-        })?
-        .with_swarm_config(|cfg| cfg.with_idle_connection_timeout(Duration::from_secs(u64::MAX)))
-        .build();
-    Ok(swarm)
+impl IdentifyEvent {
+    fn from_node_event(event: NodeEvent) -> Option<Self> {
+        let event = match event {
+            NodeEvent::Listening { addr } => IdentifyEvent::Listening { addr },
+            NodeEvent::Dialing {
+                peer_id,
+                connection_id,
+            } => IdentifyEvent::Dialing {
+                peer_id,
+                connection_id,
+            },
+            NodeEvent::DialFailed(failure) => IdentifyEvent::DialFailed(failure),
+            NodeEvent::Connected(info) => IdentifyEvent::Connected(info),
+            NodeEvent::Disconnected(closed) => IdentifyEvent::Disconnected(closed),
+            NodeEvent::MdnsDiscovered(peer) => IdentifyEvent::Discovered(peer),
+            NodeEvent::MdnsExpired(peer) => IdentifyEvent::Expired(peer),
+            NodeEvent::Ping { peer_id, rtt_ms } => IdentifyEvent::Ping { peer_id, rtt_ms },
+            NodeEvent::Identified {
+                peer_id,
+                protocol_version,
+                agent_version,
+                listen_addrs,
+            } => IdentifyEvent::Identified {
+                peer_id,
+                protocol_version,
+                agent_version,
+                listen_addrs,
+            },
+            NodeEvent::Error { message } | NodeEvent::ListenerError { message } => {
+                IdentifyEvent::Error { message }
+            }
+            _ => return None,
+        };
+        Some(event)
+    }
 }
 
 #[tauri::command]
 pub async fn start_identify_node(
-    app: AppHandle,
+    node: State<'_, NodeManager>,
+    keystore: State<'_, Keystore>,
+    bootstrap: State<'_, BootstrapList>,
+    config: Option<NodeConfig>,
     on_event: Channel<IdentifyEvent>,
 ) -> Result<String, String> {
-    let state = app.try_state::<Mutex<IdentifySwarmState>>();
-    let mut swarm = create_identify_swarm().map_err(|e| e.to_string())?;
-    let peer_id = swarm.local_peer_id().to_string();
-    let (tx, mut rx) = mpsc::channel::<IdentifyCommand>(1);
-
-    swarm
-        .listen_on("/ip4/0.0.0.0/tcp/0".parse().unwrap())
+    let peer_id = node
+        .start(
+            DEFAULT_NODE,
+            keystore.keypair().map_err(|e| e.to_string())?,
+            with_bootstrap(config, &bootstrap),
+        )
+        .await
         .map_err(|e| e.to_string())?;
-
-    if let Some(state) = state {
-        state.lock().await.0 = tx;
-    } else {
-        app.manage(Mutex::new(IdentifySwarmState(tx)));
-    }
-
-    tokio::spawn(async move {
-        loop {
-            select! {
-                command = rx.recv() => {
-                    if let Some(command) = command {
-                        match command {
-                            IdentifyCommand::Dial(addr) => { let _ = swarm.dial(addr); }
-                            IdentifyCommand::Disconnect(peer_id) => { let _ = swarm.disconnect_peer_id(peer_id); }
-                            IdentifyCommand::Stop => break,
-                        }
-                    }
-                }
-                event = swarm.select_next_some() => {
-                    let identify_event = match event {
-                        SwarmEvent::NewListenAddr { address, .. } => {
-                            IdentifyEvent::Listening { addr: address.to_string() }
-                        }
-                        SwarmEvent::ConnectionEstablished { peer_id, .. } => {
-                            IdentifyEvent::Connected { peer_id: peer_id.to_string() }
-                        }
-                        SwarmEvent::ConnectionClosed { peer_id, .. } => {
-                            IdentifyEvent::Disconnected { peer_id: peer_id.to_string() }
-                        }
-                        SwarmEvent::Behaviour(event) => match event {
-                            IdentifyBehaviourEvent::Ping(ping::Event { peer, result, .. }) => {
-                                match result {
-                                    Ok(rtt) => IdentifyEvent::Ping { peer_id: peer.to_string(), rtt_ms: rtt.as_millis() },
-                                    Err(e) => IdentifyEvent::Error { message: e.to_string() },
-                                }
-                            }
-                            IdentifyBehaviourEvent::Identify(identify::Event::Received { peer_id, info, .. }) => {
-                                IdentifyEvent::Identified {
-                                    peer_id: peer_id.to_string(),
-                                    protocol_version: info.protocol_version,
-                                    agent_version: info.agent_version,
-                                    listen_addrs: info.listen_addrs.iter().map(|a| a.to_string()).collect(),
-                                }
-                            }
-                            IdentifyBehaviourEvent::Identify(_) => continue,
-                        },
-                        _ => continue,
-                    };
-                    let _ = on_event.send(identify_event);
-                }
-            }
-        }
-    });
-
-    Ok(peer_id)
+    node.attach(DEFAULT_NODE, PAGE, on_event, IdentifyEvent::from_node_event)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(peer_id.to_string())
 }
 
+/// 返回命令执行结果；`Stop` 只是离开共享节点，没有结果
 #[tauri::command]
 pub async fn send_identify_command(
-    state: State<'_, Mutex<IdentifySwarmState>>,
+    node: State<'_, NodeManager>,
     cmd: IdentifyCommand,
-) -> tauri::Result<()> {
-    state.lock().await.0.send(cmd).await.ok();
-    Ok(())
+) -> Result<Option<CommandReply>, String> {
+    let command = match cmd {
+        IdentifyCommand::Dial(addr) => NodeCommand::Dial { addr },
+        IdentifyCommand::Disconnect(peer_id) => NodeCommand::Disconnect { peer_id },
+        // 页面停止只是离开共享节点，节点继续为其他页面服务
+        IdentifyCommand::Stop => {
+            node.detach(DEFAULT_NODE, PAGE).await;
+            return Ok(None);
+        }
+    };
+    node.send(DEFAULT_NODE, command)
+        .await
+        .map(Some)
+        .map_err(|e| e.to_string())
 }
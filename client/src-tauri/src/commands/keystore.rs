@@ -0,0 +1,79 @@
+use tauri::{AppHandle, Manager, State};
+
+use crate::keystore::{self, IdentityInfo, KeyFormat, KeyType, Keystore};
+
+/// 空口令视为不加密
+fn non_empty(passphrase: Option<String>) -> Option<String> {
+    passphrase.filter(|p| !p.is_empty())
+}
+
+/// 在阻塞线程池上操作密钥库，Argon2 派生耗时较长，不能占用主线程
+async fn with_keystore<T: Send + 'static>(
+    app: AppHandle,
+    f: impl FnOnce(&Keystore) -> anyhow::Result<T> + Send + 'static,
+) -> Result<T, String> {
+    tauri::async_runtime::spawn_blocking(move || f(&app.state::<Keystore>()))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+/// 查看本地身份，尚未生成时返回 `None`
+#[tauri::command]
+pub fn get_identity(keystore: State<'_, Keystore>) -> Result<Option<IdentityInfo>, String> {
+    keystore.info().map_err(|e| e.to_string())
+}
+
+/// 启动时身份文件损坏的原因，坏文件已被移到一旁并会生成新身份
+#[tauri::command]
+pub fn get_identity_error(keystore: State<'_, Keystore>) -> Option<String> {
+    keystore.load_error().map(str::to_owned)
+}
+
+/// 生成新的本地身份，节点重启后生效
+#[tauri::command]
+pub async fn create_identity(
+    app: AppHandle,
+    key_type: KeyType,
+    passphrase: Option<String>,
+) -> Result<IdentityInfo, String> {
+    let passphrase = non_empty(passphrase);
+    with_keystore(app, move |keystore| {
+        keystore.create(key_type, passphrase.as_deref())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn unlock_identity(app: AppHandle, passphrase: String) -> Result<IdentityInfo, String> {
+    with_keystore(app, move |keystore| keystore.unlock(&passphrase)).await
+}
+
+/// 修改身份口令，传空则改为明文保存
+#[tauri::command]
+pub async fn set_identity_passphrase(
+    app: AppHandle,
+    passphrase: Option<String>,
+) -> Result<IdentityInfo, String> {
+    let passphrase = non_empty(passphrase);
+    with_keystore(app, move |keystore| {
+        keystore.set_passphrase(passphrase.as_deref())
+    })
+    .await
+}
+
+/// 导入外部密钥作为节点身份，节点重启后生效
+#[tauri::command]
+pub async fn import_identity(
+    app: AppHandle,
+    data: String,
+    format: KeyFormat,
+    passphrase: Option<String>,
+) -> Result<IdentityInfo, String> {
+    let keypair = keystore::import_keypair(&data, format).map_err(|e| e.to_string())?;
+    let passphrase = non_empty(passphrase);
+    with_keystore(app, move |keystore| {
+        keystore.replace(keypair, passphrase.as_deref())
+    })
+    .await
+}
@@ -6,3 +6,17 @@ mod multiaddr;
 pub use multiaddr::*;
 mod identify;
 pub use identify::*;
+mod node;
+pub use node::*;
+mod keystore;
+pub use keystore::*;
+mod vanity;
+pub use vanity::*;
+mod bootstrap;
+pub use bootstrap::*;
+mod stream;
+pub use stream::*;
+mod file;
+pub use file::*;
+mod block;
+pub use block::*;
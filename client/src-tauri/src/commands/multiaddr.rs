@@ -1,4 +1,4 @@
-use libp2p::{multiaddr::Protocol, Multiaddr};
+use libp2p::{multiaddr::Protocol, Multiaddr, PeerId};
 use serde::Serialize;
 
 /// 协议层级
@@ -147,3 +147,77 @@ pub fn parse_multiaddr(input: String) -> ParsedMultiaddr {
         },
     }
 }
+
+/// 校验监听地址：需要 IP 网络层和受支持的传输（TCP、TCP/WS、UDP/QUIC），且不能带 PeerId。
+/// 经中继监听时写作 `[<中继地址>]/p2p/<中继 PeerId>/p2p-circuit`，已连接中继时可省略中继地址，
+/// 中继地址也可以用 `/dns`、`/dns4`、`/dns6` 或 `/dnsaddr` 域名
+pub fn validate_listen_addr(input: &str) -> Result<Multiaddr, String> {
+    let parsed = parse_multiaddr(input.trim().to_string());
+    if let Some(error) = parsed.error {
+        return Err(format!("Invalid multiaddr {}: {}", input, error));
+    }
+
+    let invalid = |reason: &str| Err(format!("Cannot listen on {}: {}", input, reason));
+    let (transport, relayed) = match parsed.components.as_slice() {
+        [transport @ .., relay, circuit]
+            if relay.layer == ProtocolLayer::Identity && circuit.layer == ProtocolLayer::Relay =>
+        {
+            (transport, true)
+        }
+        components => (components, false),
+    };
+    if transport
+        .iter()
+        .any(|c| c.layer == ProtocolLayer::Identity || c.layer == ProtocolLayer::Relay)
+    {
+        return invalid(
+            "listen addresses must not contain /p2p or /p2p-circuit except a trailing /p2p/<relay>/p2p-circuit",
+        );
+    }
+    let names: Vec<&str> = transport.iter().map(|c| c.name.as_str()).collect();
+    match names.as_slice() {
+        [] | ["dnsaddr"] if relayed => {}
+        [host, transport @ ..]
+            if matches!(*host, "ip4" | "ip6")
+                || (relayed && matches!(*host, "dns" | "dns4" | "dns6")) =>
+        {
+            if !matches!(transport, ["tcp"] | ["tcp", "ws"] | ["udp", "quic-v1"]) {
+                return invalid("supported transports are tcp, tcp/ws and udp/quic-v1");
+            }
+        }
+        _ if relayed => return invalid("expected an /ip4, /ip6 or /dns relay address"),
+        _ => return invalid("expected an /ip4 or /ip6 address"),
+    }
+
+    parsed.input.trim().parse().map_err(|e| format!("{}", e))
+}
+
+/// 校验引导节点地址：必须以 `/p2p/<PeerId>` 结尾，且前面带有可拨号的传输地址
+pub fn validate_bootstrap_addr(input: &str) -> Result<(PeerId, Multiaddr), String> {
+    let parsed = parse_multiaddr(input.trim().to_string());
+    if let Some(error) = parsed.error {
+        return Err(format!("Invalid multiaddr {}: {}", input, error));
+    }
+
+    let invalid = |reason: &str| Err(format!("Invalid bootstrap address {}: {}", input, reason));
+    let peer_id = match parsed.components.as_slice() {
+        [] => return invalid("address is empty"),
+        [transport @ .., last] if last.name == "p2p" => {
+            // 只有 IP 没有端口时无法拨号
+            if !transport.iter().any(|c| {
+                matches!(
+                    c.name.as_str(),
+                    "tcp" | "udp" | "dns" | "dns4" | "dns6" | "dnsaddr"
+                )
+            }) {
+                return invalid("expected a tcp, udp or dns component before /p2p");
+            }
+            last.value.as_deref().unwrap_or_default()
+        }
+        _ => return invalid("address must end with /p2p/<PeerId>"),
+    };
+    let peer_id = peer_id.parse::<PeerId>().map_err(|e| e.to_string())?;
+
+    let addr = parsed.input.trim().parse().map_err(|e| format!("{}", e))?;
+    Ok((peer_id, addr))
+}
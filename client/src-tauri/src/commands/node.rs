@@ -0,0 +1,150 @@
+use std::time::Duration;
+
+use libp2p::{identity::Keypair, PeerId};
+use serde_json::Value;
+use tauri::{ipc::Channel, State};
+
+use crate::{
+    bootstrap::BootstrapList,
+    keystore::Keystore,
+    node::{
+        CommandReply, ComparisonReport, KBucketEntry, NodeCommand, NodeConfig, NodeEvent, NodeInfo,
+        NodeManager, PeerScore, RpcReply, TopicMesh, DEFAULT_NODE,
+    },
+};
+
+/// 未指定引导节点时使用保存的引导列表
+pub(crate) fn with_bootstrap(config: Option<NodeConfig>, bootstrap: &BootstrapList) -> NodeConfig {
+    let mut config = config.unwrap_or_default();
+    config
+        .bootstrap_peers
+        .get_or_insert_with(|| bootstrap.peers());
+    config
+}
+
+/// 启动（或挂载到）节点，接收该节点的全部事件。
+/// 不传 `node_id` 时为使用密钥库身份的主节点，其他节点使用临时生成的 ed25519 身份。
+#[tauri::command]
+pub async fn start_node(
+    node: State<'_, NodeManager>,
+    keystore: State<'_, Keystore>,
+    bootstrap: State<'_, BootstrapList>,
+    node_id: Option<String>,
+    config: Option<NodeConfig>,
+    on_event: Channel<NodeEvent>,
+) -> Result<String, String> {
+    let node_id = node_id.as_deref().unwrap_or(DEFAULT_NODE);
+    let keypair = if node_id == DEFAULT_NODE {
+        keystore.keypair().map_err(|e| e.to_string())?
+    } else {
+        Keypair::generate_ed25519()
+    };
+    let peer_id = node
+        .start(node_id, keypair, with_bootstrap(config, &bootstrap))
+        .await
+        .map_err(|e| e.to_string())?;
+    node.attach(node_id, "node", on_event, Some)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(peer_id.to_string())
+}
+
+#[tauri::command]
+pub async fn send_node_command(
+    node: State<'_, NodeManager>,
+    node_id: Option<String>,
+    cmd: NodeCommand,
+) -> Result<CommandReply, String> {
+    node.send(node_id.as_deref().unwrap_or(DEFAULT_NODE), cmd)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn stop_node(
+    node: State<'_, NodeManager>,
+    node_id: Option<String>,
+) -> Result<(), String> {
+    node.stop(node_id.as_deref().unwrap_or(DEFAULT_NODE)).await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn list_nodes(node: State<'_, NodeManager>) -> Result<Vec<NodeInfo>, String> {
+    Ok(node.nodes().await)
+}
+
+/// 让 `node_id` 拨号同进程内的 `target` 节点
+#[tauri::command]
+pub async fn dial_local_node(
+    node: State<'_, NodeManager>,
+    node_id: String,
+    target: String,
+) -> Result<CommandReply, String> {
+    node.dial_node(&node_id, &target)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Kademlia 路由表快照，只包含非空的 k-bucket
+#[tauri::command]
+pub async fn get_routing_table(
+    node: State<'_, NodeManager>,
+    node_id: Option<String>,
+) -> Result<Vec<KBucketEntry>, String> {
+    node.routing_table(node_id.as_deref().unwrap_or(DEFAULT_NODE))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 已订阅话题及其 mesh 节点
+#[tauri::command]
+pub async fn get_mesh_peers(
+    node: State<'_, NodeManager>,
+    node_id: Option<String>,
+) -> Result<Vec<TopicMesh>, String> {
+    node.mesh_peers(node_id.as_deref().unwrap_or(DEFAULT_NODE))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 各话题 gossipsub 与 floodsub 的对比结果
+#[tauri::command]
+pub async fn get_pubsub_comparison(
+    node: State<'_, NodeManager>,
+    node_id: Option<String>,
+) -> Result<Vec<ComparisonReport>, String> {
+    node.pubsub_comparison(node_id.as_deref().unwrap_or(DEFAULT_NODE))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 各 gossipsub 节点的评分明细，用于查看节点为何被剪除出 mesh
+#[tauri::command]
+pub async fn get_peer_scores(
+    node: State<'_, NodeManager>,
+    node_id: Option<String>,
+) -> Result<Vec<PeerScore>, String> {
+    node.peer_scores(node_id.as_deref().unwrap_or(DEFAULT_NODE))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 通过节点的请求-响应协议向 `peer_id` 发送请求，`timeout_ms` 缺省时使用启动时配置的超时
+#[tauri::command]
+pub async fn rpc_request(
+    node: State<'_, NodeManager>,
+    node_id: Option<String>,
+    peer_id: PeerId,
+    request: Value,
+    timeout_ms: Option<u64>,
+) -> Result<RpcReply, String> {
+    node.rpc_request(
+        node_id.as_deref().unwrap_or(DEFAULT_NODE),
+        peer_id,
+        request,
+        timeout_ms.map(Duration::from_millis),
+    )
+    .await
+    .map_err(|e| e.to_string())
+}
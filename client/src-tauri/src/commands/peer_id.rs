@@ -1,5 +1,17 @@
-use libp2p::identity::Keypair;
-use serde::Serialize;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use libp2p::{
+    identity::{Keypair, PublicKey},
+    PeerId,
+};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::keystore::{self, KeyFormat, KeyType, Keystore};
+
+/// multihash 编码：identity（内嵌公钥）
+const MULTIHASH_IDENTITY: u64 = 0x00;
+/// multihash 编码：sha2-256
+const MULTIHASH_SHA2_256: u64 = 0x12;
 
 #[derive(Serialize)]
 pub struct PeerIdResult {
@@ -8,21 +20,213 @@ pub struct PeerIdResult {
     pub key_type: String,
 }
 
+impl PeerIdResult {
+    fn from_keypair(keypair: &Keypair) -> Result<Self, String> {
+        let public_key_bytes = keypair.public().encode_protobuf();
+        let key_type = KeyType::try_from(keypair.key_type()).map_err(|e| e.to_string())?;
+
+        Ok(PeerIdResult {
+            peer_id: keypair.public().to_peer_id().to_string(),
+            public_key_hex: hex::encode(&public_key_bytes),
+            key_type: key_type.to_string(),
+        })
+    }
+}
+
+/// 导出的密钥，`data` 包含私钥
+#[derive(Clone, Serialize)]
+pub struct ExportedKeypair {
+    pub peer_id: String,
+    pub key_type: String,
+    pub format: KeyFormat,
+    pub data: String,
+}
+
+impl ExportedKeypair {
+    pub(crate) fn from_keypair(keypair: &Keypair, format: KeyFormat) -> Result<Self, String> {
+        let data = keystore::export_keypair(keypair, format).map_err(|e| e.to_string())?;
+
+        Ok(ExportedKeypair {
+            peer_id: keypair.public().to_peer_id().to_string(),
+            key_type: keypair.key_type().to_string().to_lowercase(),
+            format,
+            data,
+        })
+    }
+}
+
+/// PeerId 解析结果
+#[derive(Serialize)]
+pub struct PeerIdInfo {
+    pub peer_id: String,
+    /// multihash 算法名
+    pub multihash: String,
+    pub multihash_code: u64,
+    pub digest_hex: String,
+    /// 只有 identity 哈希的 PeerId 才内嵌公钥
+    pub key_type: Option<String>,
+    pub public_key_hex: Option<String>,
+}
+
+/// 待签名消息的编码方式
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MessageEncoding {
+    #[default]
+    Text,
+    Hex,
+    Base64,
+}
+
+impl MessageEncoding {
+    fn decode(self, message: &str) -> Result<Vec<u8>, String> {
+        match self {
+            MessageEncoding::Text => Ok(message.as_bytes().to_vec()),
+            MessageEncoding::Hex => hex::decode(message.trim()).map_err(|e| e.to_string()),
+            MessageEncoding::Base64 => STANDARD.decode(message.trim()).map_err(|e| e.to_string()),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct SignatureResult {
+    pub peer_id: String,
+    pub public_key_hex: String,
+    pub signature_hex: String,
+}
+
+#[derive(Serialize)]
+pub struct VerifyResult {
+    pub valid: bool,
+    pub peer_id: String,
+    pub key_type: String,
+}
+
+/// 前端传入的密钥优先，否则使用节点当前身份
+fn resolve_keypair(keystore: &Keystore, keypair_hex: Option<String>) -> Result<Keypair, String> {
+    match keypair_hex {
+        Some(keypair_hex) => keystore::import_keypair(&keypair_hex, KeyFormat::Protobuf),
+        None => keystore.keypair(),
+    }
+    .map_err(|e| e.to_string())
+}
+
+/// 解析签名者：内嵌公钥的 PeerId，或 protobuf 编码公钥的十六进制
+fn resolve_public_key(signer: &str) -> Result<PublicKey, String> {
+    let signer = signer.trim();
+    if let Ok(peer_id) = signer.parse::<PeerId>() {
+        let multihash = peer_id.as_ref();
+        if multihash.code() != MULTIHASH_IDENTITY {
+            return Err(format!(
+                "PeerId {} does not embed its public key, pass the encoded public key instead",
+                peer_id
+            ));
+        }
+        return PublicKey::try_decode_protobuf(multihash.digest()).map_err(|e| e.to_string());
+    }
+    let bytes =
+        hex::decode(signer).map_err(|_| format!("Invalid PeerId or public key: {}", signer))?;
+    PublicKey::try_decode_protobuf(&bytes).map_err(|e| e.to_string())
+}
+
+/// 生成临时 PeerId，只返回公钥，私钥随即丢弃
 #[tauri::command]
 pub fn generate_peer_id(key_type: &str) -> Result<PeerIdResult, String> {
-    let keypair = match key_type {
-        "ed25519" => Keypair::generate_ed25519(),
-        "secp256k1" => Keypair::generate_secp256k1(),
-        "ecdsa" => Keypair::generate_ecdsa(),
-        _ => return Err(format!("Unsupported key type: {}", key_type)),
+    let keypair = key_type.parse::<KeyType>()?.generate();
+    PeerIdResult::from_keypair(&keypair)
+}
+
+/// 生成密钥并按 `format` 导出（包含私钥），protobuf 格式的 `data`
+/// 可以作为 `keypair_hex` 传给 [`export_keypair`] 和 [`sign_message`]
+#[tauri::command]
+pub fn generate_keypair(key_type: KeyType, format: KeyFormat) -> Result<ExportedKeypair, String> {
+    ExportedKeypair::from_keypair(&key_type.generate(), format)
+}
+
+/// 导出密钥；不传 `keypair_hex` 时导出节点当前身份
+#[tauri::command]
+pub fn export_keypair(
+    keystore: State<'_, Keystore>,
+    keypair_hex: Option<String>,
+    format: KeyFormat,
+) -> Result<ExportedKeypair, String> {
+    let keypair = resolve_keypair(&keystore, keypair_hex)?;
+    ExportedKeypair::from_keypair(&keypair, format)
+}
+
+/// 解析导入的密钥，不会改动节点身份
+#[tauri::command]
+pub fn import_keypair(data: String, format: KeyFormat) -> Result<PeerIdResult, String> {
+    let keypair = keystore::import_keypair(&data, format).map_err(|e| e.to_string())?;
+    PeerIdResult::from_keypair(&keypair)
+}
+
+#[tauri::command]
+pub fn inspect_peer_id(peer_id: String) -> Result<PeerIdInfo, String> {
+    let parsed = peer_id
+        .trim()
+        .parse::<PeerId>()
+        .map_err(|e| e.to_string())?;
+    let multihash = parsed.as_ref();
+
+    let public_key = match multihash.code() {
+        MULTIHASH_IDENTITY => {
+            Some(PublicKey::try_decode_protobuf(multihash.digest()).map_err(|e| e.to_string())?)
+        }
+        _ => None,
     };
+    let multihash_name = match multihash.code() {
+        MULTIHASH_IDENTITY => "identity".to_string(),
+        MULTIHASH_SHA2_256 => "sha2-256".to_string(),
+        code => format!("0x{:x}", code),
+    };
+
+    Ok(PeerIdInfo {
+        peer_id: parsed.to_string(),
+        multihash: multihash_name,
+        multihash_code: multihash.code(),
+        digest_hex: hex::encode(multihash.digest()),
+        key_type: public_key
+            .as_ref()
+            .map(|key| key.key_type().to_string().to_lowercase()),
+        public_key_hex: public_key.map(|key| hex::encode(key.encode_protobuf())),
+    })
+}
+
+/// 签名消息；不传 `keypair_hex` 时使用节点当前身份
+#[tauri::command]
+pub fn sign_message(
+    keystore: State<'_, Keystore>,
+    message: String,
+    encoding: Option<MessageEncoding>,
+    keypair_hex: Option<String>,
+) -> Result<SignatureResult, String> {
+    let message = encoding.unwrap_or_default().decode(&message)?;
+    let keypair = resolve_keypair(&keystore, keypair_hex)?;
+    let signature = keypair.sign(&message).map_err(|e| e.to_string())?;
 
-    let peer_id = keypair.public().to_peer_id();
-    let public_key_bytes = keypair.public().encode_protobuf();
+    Ok(SignatureResult {
+        peer_id: keypair.public().to_peer_id().to_string(),
+        public_key_hex: hex::encode(keypair.public().encode_protobuf()),
+        signature_hex: hex::encode(signature),
+    })
+}
+
+/// 用 PeerId 或编码后的公钥校验签名
+#[tauri::command]
+pub fn verify_signature(
+    message: String,
+    encoding: Option<MessageEncoding>,
+    signature_hex: String,
+    signer: String,
+) -> Result<VerifyResult, String> {
+    let message = encoding.unwrap_or_default().decode(&message)?;
+    let signature = hex::decode(signature_hex.trim()).map_err(|e| e.to_string())?;
+    let public_key = resolve_public_key(&signer)?;
 
-    Ok(PeerIdResult {
-        peer_id: peer_id.to_string(),
-        public_key_hex: hex::encode(&public_key_bytes),
-        key_type: key_type.to_string(),
+    Ok(VerifyResult {
+        valid: public_key.verify(&message, &signature),
+        peer_id: public_key.to_peer_id().to_string(),
+        key_type: public_key.key_type().to_string().to_lowercase(),
     })
 }
@@ -1,20 +1,19 @@
-use std::{
-    ops::{Deref, DerefMut},
-    time::Duration,
-};
-
-use anyhow::Result;
-use libp2p::{
-    futures::StreamExt, noise, ping, swarm::SwarmEvent, tcp, yamux, Multiaddr, PeerId, Swarm,
-    SwarmBuilder,
-};
+use libp2p::{Multiaddr, PeerId};
 use serde::{Deserialize, Serialize};
-use tauri::{ipc::Channel, AppHandle, Manager, State};
-use tokio::{
-    select,
-    sync::{mpsc, Mutex},
+use tauri::{ipc::Channel, State};
+
+use super::node::with_bootstrap;
+use crate::{
+    bootstrap::BootstrapList,
+    keystore::Keystore,
+    node::{
+        ClosedConnection, CommandReply, ConnectionInfo, DialFailure, DiscoveredPeer, NodeCommand,
+        NodeConfig, NodeEvent, NodeManager, DEFAULT_NODE,
+    },
 };
 
+const PAGE: &str = "ping";
+
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 #[serde(tag = "command", content = "addr")]
@@ -31,13 +30,16 @@ pub enum PingEvent {
         addr: String,
     },
     #[serde(rename_all = "camelCase")]
-    Connected {
-        peer_id: String,
-    },
-    #[serde(rename_all = "camelCase")]
-    Disconnected {
-        peer_id: String,
+    Dialing {
+        peer_id: Option<String>,
+        connection_id: String,
     },
+    DialFailed(DialFailure),
+    Connected(ConnectionInfo),
+    Disconnected(ClosedConnection),
+    /// 局域网内通过 mDNS 发现的节点，可直接拨号其地址
+    Discovered(DiscoveredPeer),
+    Expired(DiscoveredPeer),
     #[serde(rename_all = "camelCase")]
     Ping {
         peer_id: String,
@@ -48,110 +50,71 @@ pub enum PingEvent {
     },
 }
 
-pub struct PingSwarm {
-    swarm: Swarm<ping::Behaviour>,
-}
-
-impl Deref for PingSwarm {
-    type Target = Swarm<ping::Behaviour>;
-    fn deref(&self) -> &Self::Target {
-        &self.swarm
-    }
-}
-
-impl DerefMut for PingSwarm {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.swarm
-    }
-}
-
-impl PingSwarm {
-    pub fn new() -> Result<Self> {
-        let swarm = SwarmBuilder::with_new_identity()
-            .with_tokio()
-            .with_tcp(
-                tcp::Config::default(),
-                noise::Config::new,
-                yamux::Config::default,
-            )?
-            .with_behaviour(|_| ping::Behaviour::default())?
-            .with_swarm_config(|cfg| {
-                cfg.with_idle_connection_timeout(Duration::from_secs(u64::MAX))
-            })
-            .build();
-        Ok(Self { swarm })
+impl PingEvent {
+    fn from_node_event(event: NodeEvent) -> Option<Self> {
+        let event = match event {
+            NodeEvent::Listening { addr } => PingEvent::Listening { addr },
+            NodeEvent::Dialing {
+                peer_id,
+                connection_id,
+            } => PingEvent::Dialing {
+                peer_id,
+                connection_id,
+            },
+            NodeEvent::DialFailed(failure) => PingEvent::DialFailed(failure),
+            NodeEvent::Connected(info) => PingEvent::Connected(info),
+            NodeEvent::Disconnected(closed) => PingEvent::Disconnected(closed),
+            NodeEvent::MdnsDiscovered(peer) => PingEvent::Discovered(peer),
+            NodeEvent::MdnsExpired(peer) => PingEvent::Expired(peer),
+            NodeEvent::Ping { peer_id, rtt_ms } => PingEvent::Ping { peer_id, rtt_ms },
+            NodeEvent::Error { message } | NodeEvent::ListenerError { message } => {
+                PingEvent::Error { message }
+            }
+            _ => return None,
+        };
+        Some(event)
     }
 }
 
 #[tauri::command]
 pub async fn start_ping_node(
-    app: AppHandle,
+    node: State<'_, NodeManager>,
+    keystore: State<'_, Keystore>,
+    bootstrap: State<'_, BootstrapList>,
+    config: Option<NodeConfig>,
     on_event: Channel<PingEvent>,
 ) -> Result<String, String> {
-    let state_tx = app.try_state::<Mutex<mpsc::Sender<PingCommand>>>();
-    let mut ping_swarm = PingSwarm::new().map_err(|e| e.to_string())?;
-    let peer_id = ping_swarm.local_peer_id().to_string();
-    let (tx, mut rx) = mpsc::channel::<PingCommand>(1);
-    // 使用 0 端口让系统自动分配可用端口
-    ping_swarm
-        .listen_on("/ip4/0.0.0.0/tcp/0".parse().unwrap())
+    let peer_id = node
+        .start(
+            DEFAULT_NODE,
+            keystore.keypair().map_err(|e| e.to_string())?,
+            with_bootstrap(config, &bootstrap),
+        )
+        .await
         .map_err(|e| e.to_string())?;
-
-    if let Some(state_tx) = state_tx {
-        *state_tx.lock().await = tx;
-    } else {
-        app.manage(Mutex::new(tx));
-    }
-
-    tokio::spawn(async move {
-        loop {
-            select! {
-                command=rx.recv()=>{
-                    if let Some(command)=command{
-                        match command{
-                            PingCommand::Dial(multiaddr) => {
-                                let _ = ping_swarm.dial(multiaddr);
-                            },
-                            PingCommand::Disconnect(peer_id) => {
-                                let _ = ping_swarm.disconnect_peer_id(peer_id);
-                            },
-                            PingCommand::Stop => break,
-                        }
-                    }
-                }
-                event = ping_swarm.select_next_some() => {
-                    let ping_event = match event {
-                        SwarmEvent::NewListenAddr { address, .. } => {
-                            PingEvent::Listening { addr: address.to_string() }
-                        }
-                        SwarmEvent::ConnectionEstablished { peer_id, .. } => {
-                            PingEvent::Connected { peer_id: peer_id.to_string() }
-                        }
-                        SwarmEvent::ConnectionClosed { peer_id, .. } => {
-                            PingEvent::Disconnected { peer_id: peer_id.to_string() }
-                        }
-                        SwarmEvent::Behaviour(ping::Event { peer, result, .. }) => {
-                            match result {
-                                Ok(rtt) => PingEvent::Ping { peer_id: peer.to_string(), rtt_ms: rtt.as_millis() },
-                                Err(e) => PingEvent::Error { message: e.to_string() },
-                            }
-                        }
-                        _ => continue,
-                    };
-                    let _ = on_event.send(ping_event);
-                }
-            }
-        }
-    });
-
-    Ok(peer_id)
+    node.attach(DEFAULT_NODE, PAGE, on_event, PingEvent::from_node_event)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(peer_id.to_string())
 }
 
+/// 返回命令执行结果；`Stop` 只是离开共享节点，没有结果
 #[tauri::command]
 pub async fn send_ping_command(
-    cmd_tx: State<'_, Mutex<mpsc::Sender<PingCommand>>>,
+    node: State<'_, NodeManager>,
     cmd: PingCommand,
-) -> tauri::Result<()> {
-    cmd_tx.lock().await.send(cmd).await.ok();
-    Ok(())
+) -> Result<Option<CommandReply>, String> {
+    let command = match cmd {
+        PingCommand::Dial(addr) => NodeCommand::Dial { addr },
+        PingCommand::Disconnect(peer_id) => NodeCommand::Disconnect { peer_id },
+        // 页面停止只是离开共享节点，节点继续为其他页面服务
+        PingCommand::Stop => {
+            node.detach(DEFAULT_NODE, PAGE).await;
+            return Ok(None);
+        }
+    };
+    node.send(DEFAULT_NODE, command)
+        .await
+        .map(Some)
+        .map_err(|e| e.to_string())
 }
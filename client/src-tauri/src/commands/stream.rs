@@ -0,0 +1,101 @@
+use libp2p::PeerId;
+use tauri::State;
+
+use crate::node::{
+    NodeManager, StreamHandler, StreamInfo, StreamOptions, StreamsSnapshot, DEFAULT_NODE,
+};
+
+/// 在 `protocol` 上打开到 `peer_id` 的原始流，读到的数据通过节点的 `stream` 事件推送
+#[tauri::command]
+pub async fn open_stream(
+    node: State<'_, NodeManager>,
+    node_id: Option<String>,
+    peer_id: PeerId,
+    protocol: String,
+    options: Option<StreamOptions>,
+) -> Result<StreamInfo, String> {
+    let streams = node
+        .streams(node_id.as_deref().unwrap_or(DEFAULT_NODE))
+        .await
+        .map_err(|e| e.to_string())?;
+    streams
+        .open(peer_id, &protocol, options.unwrap_or_default())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 按流的编码和分帧方式写入一条数据，返回写入的原始字节数
+#[tauri::command]
+pub async fn write_stream(
+    node: State<'_, NodeManager>,
+    node_id: Option<String>,
+    stream_id: u64,
+    data: String,
+) -> Result<usize, String> {
+    let streams = node
+        .streams(node_id.as_deref().unwrap_or(DEFAULT_NODE))
+        .await
+        .map_err(|e| e.to_string())?;
+    streams
+        .write(stream_id, &data)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 关闭流的写端
+#[tauri::command]
+pub async fn close_stream(
+    node: State<'_, NodeManager>,
+    node_id: Option<String>,
+    stream_id: u64,
+) -> Result<(), String> {
+    let streams = node
+        .streams(node_id.as_deref().unwrap_or(DEFAULT_NODE))
+        .await
+        .map_err(|e| e.to_string())?;
+    streams.close(stream_id).await.map_err(|e| e.to_string())
+}
+
+/// 注册入站协议，之后对方在该协议上打开的流按 `handler` 处理
+#[tauri::command]
+pub async fn accept_streams(
+    node: State<'_, NodeManager>,
+    node_id: Option<String>,
+    protocol: String,
+    handler: StreamHandler,
+    options: Option<StreamOptions>,
+) -> Result<(), String> {
+    let streams = node
+        .streams(node_id.as_deref().unwrap_or(DEFAULT_NODE))
+        .await
+        .map_err(|e| e.to_string())?;
+    streams
+        .accept(&protocol, handler, options.unwrap_or_default())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn stop_accepting_streams(
+    node: State<'_, NodeManager>,
+    node_id: Option<String>,
+    protocol: String,
+) -> Result<(), String> {
+    let streams = node
+        .streams(node_id.as_deref().unwrap_or(DEFAULT_NODE))
+        .await
+        .map_err(|e| e.to_string())?;
+    streams.stop_accepting(&protocol).map_err(|e| e.to_string())
+}
+
+/// 打开的流和已注册的入站协议
+#[tauri::command]
+pub async fn list_streams(
+    node: State<'_, NodeManager>,
+    node_id: Option<String>,
+) -> Result<StreamsSnapshot, String> {
+    let streams = node
+        .streams(node_id.as_deref().unwrap_or(DEFAULT_NODE))
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(streams.snapshot())
+}
@@ -0,0 +1,348 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use libp2p::{identity::Keypair, PeerId};
+use serde::{Deserialize, Serialize};
+use tauri::{ipc::Channel, State};
+
+use super::ExportedKeypair;
+use crate::keystore::{KeyFormat, KeyType};
+
+/// base58btc 字母表
+const BASE58_ALPHABET: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 当前搜索任务的取消标记
+#[derive(Default)]
+pub struct VanitySearch(Mutex<Option<Arc<AtomicBool>>>);
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VanityOptions {
+    pub key_type: KeyType,
+    /// 紧跟在固定头部（如 ed25519 的 `12D3KooW`）之后的前缀
+    #[serde(default)]
+    pub prefix: String,
+    #[serde(default)]
+    pub suffix: String,
+    #[serde(default)]
+    pub case_insensitive: bool,
+    /// 找到后导出密钥的格式，与 `generate_keypair` 相同
+    #[serde(default)]
+    pub format: KeyFormat,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum VanityEvent {
+    #[serde(rename_all = "camelCase")]
+    Started {
+        workers: usize,
+        /// 期望尝试次数
+        expected_attempts: f64,
+    },
+    #[serde(rename_all = "camelCase")]
+    Progress {
+        attempts: u64,
+        attempts_per_sec: f64,
+        /// 按当前速度估算的剩余秒数，还没有尝试时为空
+        estimated_secs: Option<f64>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Found {
+        peer_id: String,
+        /// 包含私钥，按 [`VanityOptions::format`] 导出
+        keypair: ExportedKeypair,
+        attempts: u64,
+        elapsed_ms: u128,
+    },
+    Cancelled {
+        attempts: u64,
+    },
+    Error {
+        message: String,
+    },
+}
+
+/// 各密钥类型 PeerId 的固定头部，由 multihash 和公钥 protobuf 前缀决定
+fn fixed_header(key_type: KeyType) -> &'static str {
+    match key_type {
+        KeyType::Ed25519 => "12D3KooW",
+        KeyType::Secp256k1 => "16Uiu2HA",
+        KeyType::Ecdsa => "Qm",
+    }
+}
+
+/// PeerId 可能取到的最小值和最大值（base58）。
+///
+/// multihash 头和公钥 protobuf 前缀固定，只有公钥部分随密钥变化；
+/// secp256k1 压缩公钥的首字节只会是 0x02 或 0x03。
+fn peer_id_bounds(key_type: KeyType) -> (String, String) {
+    let (head, first, key_len): (&[u8], [u8; 2], usize) = match key_type {
+        KeyType::Ed25519 => (&[0x00, 0x24, 0x08, 0x01, 0x12, 0x20], [0x00, 0xff], 32),
+        KeyType::Secp256k1 => (&[0x00, 0x25, 0x08, 0x02, 0x12, 0x21], [0x02, 0x03], 33),
+        KeyType::Ecdsa => (&[0x12, 0x20], [0x00, 0xff], 32),
+    };
+    let encode = |first: u8, fill: u8| {
+        let mut bytes = head.to_vec();
+        bytes.push(first);
+        bytes.resize(head.len() + key_len, fill);
+        PeerId::from_bytes(&bytes)
+            .expect("fixed PeerId layout is a valid multihash")
+            .to_base58()
+    };
+    (encode(first[0], 0x00), encode(first[1], 0xff))
+}
+
+fn base58_index(c: char) -> Option<usize> {
+    BASE58_ALPHABET.find(c)
+}
+
+/// 把 base58 串按数值换算为浮点数，只用于估算概率
+fn base58_value(s: &str) -> f64 {
+    s.chars()
+        .filter_map(base58_index)
+        .fold(0.0, |acc, digit| acc * 58.0 + digit as f64)
+}
+
+/// 固定头部之后第一个字符的分布。
+///
+/// PeerId 的高位已被固定，紧跟头部的字符只能落在很窄的范围内，
+/// 每个字符的概率等于它覆盖的数值区间占 PeerId 取值区间的比例。
+struct FirstChar {
+    header_len: usize,
+    min: String,
+    max: String,
+    low: f64,
+    high: f64,
+    /// 头部后第一位上一个字符对应的数值跨度
+    unit: f64,
+}
+
+impl FirstChar {
+    fn new(key_type: KeyType) -> Self {
+        let header_len = fixed_header(key_type).len();
+        let (min, max) = peer_id_bounds(key_type);
+        debug_assert_eq!(min.len(), max.len());
+        let unit = 58f64.powi((min.len() - header_len - 1) as i32);
+        Self {
+            header_len,
+            low: base58_value(&min[header_len..]),
+            high: base58_value(&max[header_len..]) + 1.0,
+            min,
+            max,
+            unit,
+        }
+    }
+
+    fn probability(&self, c: char) -> f64 {
+        let Some(digit) = base58_index(c) else {
+            return 0.0;
+        };
+        let from = digit as f64 * self.unit;
+        let to = from + self.unit;
+        (to.min(self.high) - from.max(self.low)).max(0.0) / (self.high - self.low)
+    }
+
+    /// 第一个字符与 `c` 匹配的概率，大小写不敏感时累加所有等价字符
+    fn match_probability(&self, c: char, case_insensitive: bool) -> f64 {
+        case_variants(c, case_insensitive)
+            .into_iter()
+            .map(|c| self.probability(c))
+            .sum()
+    }
+
+    /// 可能出现的第一个字符范围
+    fn range(&self) -> (char, char) {
+        let at = |s: &str| s[self.header_len..].chars().next().unwrap_or('1');
+        (at(&self.min), at(&self.max))
+    }
+}
+
+/// 大小写不敏感时与 `c` 等价的所有 base58 字符
+fn case_variants(c: char, case_insensitive: bool) -> Vec<char> {
+    if case_insensitive {
+        BASE58_ALPHABET
+            .chars()
+            .filter(|a| a.eq_ignore_ascii_case(&c))
+            .collect()
+    } else {
+        vec![c]
+    }
+}
+
+struct Matcher {
+    prefix: String,
+    suffix: String,
+    case_insensitive: bool,
+}
+
+impl Matcher {
+    fn new(options: &VanityOptions) -> Result<Self, String> {
+        if options.prefix.is_empty() && options.suffix.is_empty() {
+            return Err("Prefix or suffix is required".to_string());
+        }
+        if let Some(c) = options
+            .prefix
+            .chars()
+            .chain(options.suffix.chars())
+            .find(|c| !BASE58_ALPHABET.contains(*c))
+        {
+            return Err(format!("'{}' is not a base58 character", c));
+        }
+        if let Some(c) = options.prefix.chars().next() {
+            let first = FirstChar::new(options.key_type);
+            if first.match_probability(c, options.case_insensitive) == 0.0 {
+                let (low, high) = first.range();
+                return Err(format!(
+                    "{} PeerIds cannot have '{}' after {}, the prefix must start with a character between '{}' and '{}'",
+                    options.key_type,
+                    c,
+                    fixed_header(options.key_type),
+                    low,
+                    high
+                ));
+            }
+        }
+        let normalize = |s: &str| {
+            if options.case_insensitive {
+                s.to_lowercase()
+            } else {
+                s.to_string()
+            }
+        };
+        Ok(Self {
+            prefix: format!(
+                "{}{}",
+                normalize(fixed_header(options.key_type)),
+                normalize(&options.prefix)
+            ),
+            suffix: normalize(&options.suffix),
+            case_insensitive: options.case_insensitive,
+        })
+    }
+
+    fn matches(&self, peer_id: &str) -> bool {
+        if self.case_insensitive {
+            let peer_id = peer_id.to_lowercase();
+            peer_id.starts_with(&self.prefix) && peer_id.ends_with(&self.suffix)
+        } else {
+            peer_id.starts_with(&self.prefix) && peer_id.ends_with(&self.suffix)
+        }
+    }
+
+    /// 期望尝试次数。头部后的第一个字符按实际分布计算，其余字符视为均匀分布
+    fn expected_attempts(options: &VanityOptions) -> f64 {
+        let uniform = |c: char| {
+            BASE58_ALPHABET.len() as f64 / case_variants(c, options.case_insensitive).len() as f64
+        };
+        let mut prefix = options.prefix.chars();
+        let first = prefix.next().map_or(1.0, |c| {
+            1.0 / FirstChar::new(options.key_type).match_probability(c, options.case_insensitive)
+        });
+        first
+            * prefix
+                .chain(options.suffix.chars())
+                .map(uniform)
+                .product::<f64>()
+    }
+}
+
+fn run_search(
+    options: VanityOptions,
+    matcher: Matcher,
+    cancel: Arc<AtomicBool>,
+    on_event: Channel<VanityEvent>,
+) {
+    let workers = thread::available_parallelism().map_or(1, |n| n.get());
+    let expected = Matcher::expected_attempts(&options);
+    let attempts = AtomicU64::new(0);
+    let found = Mutex::new(None::<Keypair>);
+    let started = Instant::now();
+    let _ = on_event.send(VanityEvent::Started {
+        workers,
+        expected_attempts: expected,
+    });
+
+    let done = || cancel.load(Ordering::Relaxed);
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| {
+                while !done() {
+                    let keypair = options.key_type.generate();
+                    attempts.fetch_add(1, Ordering::Relaxed);
+                    if matcher.matches(&keypair.public().to_peer_id().to_string()) {
+                        found
+                            .lock()
+                            .unwrap_or_else(|e| e.into_inner())
+                            .get_or_insert(keypair);
+                        cancel.store(true, Ordering::Relaxed);
+                    }
+                }
+            });
+        }
+
+        while !done() {
+            thread::sleep(PROGRESS_INTERVAL);
+            let attempts = attempts.load(Ordering::Relaxed);
+            let rate = attempts as f64 / started.elapsed().as_secs_f64();
+            let _ = on_event.send(VanityEvent::Progress {
+                attempts,
+                attempts_per_sec: rate,
+                estimated_secs: (attempts > 0)
+                    .then(|| ((expected - attempts as f64) / rate).max(0.0)),
+            });
+        }
+    });
+
+    let attempts = attempts.load(Ordering::Relaxed);
+    let event = match found.into_inner().unwrap_or_else(|e| e.into_inner()) {
+        Some(keypair) => match ExportedKeypair::from_keypair(&keypair, options.format) {
+            Ok(exported) => VanityEvent::Found {
+                peer_id: exported.peer_id.clone(),
+                keypair: exported,
+                attempts,
+                elapsed_ms: started.elapsed().as_millis(),
+            },
+            Err(message) => VanityEvent::Error { message },
+        },
+        None => VanityEvent::Cancelled { attempts },
+    };
+    let _ = on_event.send(event);
+}
+
+/// 在所有 CPU 核心上搜索符合前缀/后缀的 PeerId，进度通过 `on_event` 推送。
+/// 同时只运行一个搜索，重新开始会取消上一次。
+#[tauri::command]
+pub fn start_vanity_search(
+    state: State<'_, VanitySearch>,
+    options: VanityOptions,
+    on_event: Channel<VanityEvent>,
+) -> Result<(), String> {
+    let matcher = Matcher::new(&options)?;
+    let cancel = Arc::new(AtomicBool::new(false));
+    if let Some(previous) = state
+        .0
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .replace(cancel.clone())
+    {
+        previous.store(true, Ordering::Relaxed);
+    }
+
+    thread::spawn(move || run_search(options, matcher, cancel, on_event));
+    Ok(())
+}
+
+#[tauri::command]
+pub fn cancel_vanity_search(state: State<'_, VanitySearch>) {
+    if let Some(cancel) = state.0.lock().unwrap_or_else(|e| e.into_inner()).take() {
+        cancel.store(true, Ordering::Relaxed);
+    }
+}
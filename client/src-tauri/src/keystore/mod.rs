@@ -0,0 +1,380 @@
+mod pkcs8;
+
+use std::{
+    fmt,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use argon2::Argon2;
+use chacha20poly1305::{
+    aead::{rand_core::RngCore, Aead, KeyInit, OsRng, Payload},
+    ChaCha20Poly1305, Nonce,
+};
+use libp2p::identity::{self, Keypair, PublicKey};
+use serde::{Deserialize, Serialize};
+
+/// 密钥文件名，位于应用数据目录下
+const KEY_FILE: &str = "identity.key";
+/// 文件头魔数 + 格式版本
+const MAGIC: &[u8; 4] = b"SBK1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// 支持的密钥类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyType {
+    Ed25519,
+    Secp256k1,
+    Ecdsa,
+}
+
+impl KeyType {
+    pub fn generate(self) -> Keypair {
+        match self {
+            KeyType::Ed25519 => Keypair::generate_ed25519(),
+            KeyType::Secp256k1 => Keypair::generate_secp256k1(),
+            KeyType::Ecdsa => Keypair::generate_ecdsa(),
+        }
+    }
+}
+
+impl FromStr for KeyType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ed25519" => Ok(KeyType::Ed25519),
+            "secp256k1" => Ok(KeyType::Secp256k1),
+            "ecdsa" => Ok(KeyType::Ecdsa),
+            _ => Err(format!("Unsupported key type: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for KeyType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            KeyType::Ed25519 => "ed25519",
+            KeyType::Secp256k1 => "secp256k1",
+            KeyType::Ecdsa => "ecdsa",
+        })
+    }
+}
+
+impl TryFrom<identity::KeyType> for KeyType {
+    type Error = anyhow::Error;
+
+    fn try_from(key_type: identity::KeyType) -> Result<Self> {
+        match key_type {
+            identity::KeyType::Ed25519 => Ok(KeyType::Ed25519),
+            identity::KeyType::Secp256k1 => Ok(KeyType::Secp256k1),
+            identity::KeyType::Ecdsa => Ok(KeyType::Ecdsa),
+            other => Err(anyhow!("Unsupported key type: {}", other)),
+        }
+    }
+}
+
+/// 密钥导入导出格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyFormat {
+    /// libp2p protobuf 编码，十六进制字符串
+    #[default]
+    Protobuf,
+    /// PKCS#8 PEM
+    Pem,
+}
+
+/// 按指定格式导出密钥（包含私钥）
+pub fn export_keypair(keypair: &Keypair, format: KeyFormat) -> Result<String> {
+    match format {
+        KeyFormat::Protobuf => Ok(hex::encode(keypair.to_protobuf_encoding()?)),
+        KeyFormat::Pem => pkcs8::encode_pem(keypair),
+    }
+}
+
+/// 按指定格式导入密钥，只接受 [`KeyType`] 中的类型
+pub fn import_keypair(data: &str, format: KeyFormat) -> Result<Keypair> {
+    let keypair = match format {
+        KeyFormat::Protobuf => Keypair::from_protobuf_encoding(&hex::decode(data.trim())?)?,
+        KeyFormat::Pem => pkcs8::decode_pem(data)?,
+    };
+    KeyType::try_from(keypair.key_type())?;
+    Ok(keypair)
+}
+
+/// 本地身份概要
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IdentityInfo {
+    pub peer_id: String,
+    pub key_type: KeyType,
+    /// 是否使用口令加密保存
+    pub encrypted: bool,
+    /// 私钥是否已加载到内存
+    pub unlocked: bool,
+}
+
+/// 已加载的身份。加密的密钥在解锁前只有公钥可用
+struct StoredIdentity {
+    public: PublicKey,
+    keypair: Option<Keypair>,
+    encrypted: bool,
+}
+
+impl StoredIdentity {
+    fn info(&self) -> Result<IdentityInfo> {
+        Ok(IdentityInfo {
+            peer_id: self.public.to_peer_id().to_string(),
+            key_type: self.public.key_type().try_into()?,
+            encrypted: self.encrypted,
+            unlocked: self.keypair.is_some(),
+        })
+    }
+}
+
+/// 节点身份的磁盘存储。
+///
+/// 文件格式：`MAGIC | 公钥长度 (u16 BE) | 公钥 protobuf | 加密标记 (u8) | 私钥`，
+/// 加密时私钥部分为 `salt | nonce | ChaCha20-Poly1305 密文`，密钥由口令经 Argon2 派生。
+/// 公钥以明文保存，这样未解锁时也能显示 PeerId。
+pub struct Keystore {
+    path: PathBuf,
+    identity: Mutex<Option<StoredIdentity>>,
+    /// 启动时密钥文件无法读取的原因，坏文件已被移到一旁
+    load_error: Option<String>,
+}
+
+impl Keystore {
+    /// 打开应用数据目录下的密钥文件，未加密的密钥会直接加载。
+    ///
+    /// 文件损坏时不阻止应用启动：坏文件改名保留，随后按首次运行处理并生成新身份，
+    /// 原因可通过 [`Keystore::load_error`] 查看。
+    pub fn open(dir: &Path) -> Self {
+        let path = dir.join(KEY_FILE);
+        let (identity, load_error) = match load(&path) {
+            Ok(identity) => (identity, None),
+            Err(e) => {
+                let message = match move_aside(&path) {
+                    Ok(backup) => format!("{e:#}; the file was moved to {}", backup.display()),
+                    Err(move_err) => format!("{e:#}; failed to move the file aside: {move_err}"),
+                };
+                tracing::warn!("discarding unreadable identity: {message}");
+                (None, Some(message))
+            }
+        };
+        Self {
+            path,
+            identity: Mutex::new(identity),
+            load_error,
+        }
+    }
+
+    pub fn info(&self) -> Result<Option<IdentityInfo>> {
+        self.lock().as_ref().map(StoredIdentity::info).transpose()
+    }
+
+    /// 启动时身份文件加载失败的原因
+    pub fn load_error(&self) -> Option<&str> {
+        self.load_error.as_deref()
+    }
+
+    /// 取出节点使用的密钥；首次运行时生成 ed25519 密钥并保存
+    pub fn keypair(&self) -> Result<Keypair> {
+        let mut identity = self.lock();
+        match identity.as_ref() {
+            Some(StoredIdentity {
+                keypair: Some(keypair),
+                ..
+            }) => Ok(keypair.clone()),
+            Some(_) => bail!("Identity is locked, unlock it with its passphrase first"),
+            None => {
+                let keypair = KeyType::Ed25519.generate();
+                *identity = Some(self.save(keypair.clone(), None)?);
+                Ok(keypair)
+            }
+        }
+    }
+
+    /// 生成新的密钥并覆盖旧身份，正在运行的节点需重启后生效
+    pub fn create(&self, key_type: KeyType, passphrase: Option<&str>) -> Result<IdentityInfo> {
+        self.replace(key_type.generate(), passphrase)
+    }
+
+    /// 保存给定密钥并覆盖旧身份
+    pub fn replace(&self, keypair: Keypair, passphrase: Option<&str>) -> Result<IdentityInfo> {
+        KeyType::try_from(keypair.key_type())?;
+        let stored = self.save(keypair, passphrase)?;
+        let info = stored.info()?;
+        *self.lock() = Some(stored);
+        Ok(info)
+    }
+
+    pub fn unlock(&self, passphrase: &str) -> Result<IdentityInfo> {
+        let bytes = fs::read(&self.path)
+            .with_context(|| format!("Failed to read {}", self.path.display()))?;
+        let stored = decode(&bytes, Some(passphrase))?;
+        let info = stored.info()?;
+        *self.lock() = Some(stored);
+        Ok(info)
+    }
+
+    /// 修改口令，传入 `None` 则改为明文保存
+    pub fn set_passphrase(&self, passphrase: Option<&str>) -> Result<IdentityInfo> {
+        let keypair = self.keypair()?;
+        self.replace(keypair, passphrase)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<StoredIdentity>> {
+        self.identity.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn save(&self, keypair: Keypair, passphrase: Option<&str>) -> Result<StoredIdentity> {
+        let bytes = encode(&keypair, passphrase)?;
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        // 先写临时文件再改名，中途失败不会留下截断的身份文件
+        let tmp = self.path.with_extension("key.tmp");
+        write_private(&tmp, &bytes)
+            .and_then(|()| fs::rename(&tmp, &self.path))
+            .inspect_err(|_| {
+                let _ = fs::remove_file(&tmp);
+            })
+            .with_context(|| format!("Failed to write {}", self.path.display()))?;
+        Ok(StoredIdentity {
+            public: keypair.public(),
+            keypair: Some(keypair),
+            encrypted: passphrase.is_some(),
+        })
+    }
+}
+
+fn load(path: &Path) -> Result<Option<StoredIdentity>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let bytes = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    decode(&bytes, None).map(Some)
+}
+
+/// 把无法读取的身份文件改名为 `identity.key.<时间戳>.corrupt`
+fn move_aside(path: &Path) -> std::io::Result<PathBuf> {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let backup = path.with_file_name(format!("{KEY_FILE}.{secs}.corrupt"));
+    fs::rename(path, &backup)?;
+    Ok(backup)
+}
+
+/// 写入只有当前用户可读写的文件，文件里可能是明文私钥
+fn write_private(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    // 可能残留上次失败的临时文件，删掉以确保权限在创建时生效
+    let _ = fs::remove_file(path);
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path)?;
+    file.write_all(bytes)?;
+    file.sync_all()
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<ChaCha20Poly1305> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| anyhow!("Failed to derive key: {}", e))?;
+    Ok(ChaCha20Poly1305::new(&key.into()))
+}
+
+fn encode(keypair: &Keypair, passphrase: Option<&str>) -> Result<Vec<u8>> {
+    let public = keypair.public().encode_protobuf();
+    let secret = keypair.to_protobuf_encoding()?;
+
+    let mut bytes = MAGIC.to_vec();
+    bytes.extend_from_slice(&(public.len() as u16).to_be_bytes());
+    bytes.extend_from_slice(&public);
+    match passphrase {
+        None => {
+            bytes.push(0);
+            bytes.extend_from_slice(&secret);
+        }
+        Some(passphrase) => {
+            let mut salt = [0u8; SALT_LEN];
+            let mut nonce = [0u8; NONCE_LEN];
+            OsRng.fill_bytes(&mut salt);
+            OsRng.fill_bytes(&mut nonce);
+            // 公钥作为附加数据，防止明文部分被替换
+            let ciphertext = derive_key(passphrase, &salt)?
+                .encrypt(
+                    Nonce::from_slice(&nonce),
+                    Payload {
+                        msg: &secret,
+                        aad: &public,
+                    },
+                )
+                .map_err(|_| anyhow!("Failed to encrypt identity"))?;
+            bytes.push(1);
+            bytes.extend_from_slice(&salt);
+            bytes.extend_from_slice(&nonce);
+            bytes.extend_from_slice(&ciphertext);
+        }
+    }
+    Ok(bytes)
+}
+
+fn decode(bytes: &[u8], passphrase: Option<&str>) -> Result<StoredIdentity> {
+    let invalid = || anyhow!("Invalid identity file");
+    let rest = bytes.strip_prefix(MAGIC).ok_or_else(invalid)?;
+    let (len, rest) = rest.split_first_chunk::<2>().ok_or_else(invalid)?;
+    let len = u16::from_be_bytes(*len) as usize;
+    if rest.len() <= len {
+        return Err(invalid());
+    }
+    let (public_bytes, rest) = rest.split_at(len);
+    let (flag, secret) = rest.split_first().ok_or_else(invalid)?;
+    let public = PublicKey::try_decode_protobuf(public_bytes)?;
+
+    let (keypair, encrypted) = match (flag, passphrase) {
+        (0, _) => (Some(Keypair::from_protobuf_encoding(secret)?), false),
+        (1, None) => (None, true),
+        (1, Some(passphrase)) => {
+            if secret.len() < SALT_LEN + NONCE_LEN {
+                return Err(invalid());
+            }
+            let (salt, rest) = secret.split_at(SALT_LEN);
+            let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+            let plaintext = derive_key(passphrase, salt)?
+                .decrypt(
+                    Nonce::from_slice(nonce),
+                    Payload {
+                        msg: ciphertext,
+                        aad: public_bytes,
+                    },
+                )
+                .map_err(|_| anyhow!("Wrong passphrase"))?;
+            (Some(Keypair::from_protobuf_encoding(&plaintext)?), true)
+        }
+        _ => return Err(invalid()),
+    };
+
+    if let Some(keypair) = &keypair {
+        if keypair.public() != public {
+            bail!("Identity file is corrupted: public key mismatch");
+        }
+    }
+    Ok(StoredIdentity {
+        public,
+        keypair,
+        encrypted,
+    })
+}
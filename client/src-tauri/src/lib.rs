@@ -1,8 +1,23 @@
+pub mod bootstrap;
 pub mod commands;
+pub mod keystore;
+pub mod node;
+use bootstrap::BootstrapList;
 use commands::{
-    generate_peer_id, parse_multiaddr, send_identify_command, send_ping_command,
-    start_identify_node, start_ping_node,
+    accept_streams, add_bootstrap_peer, add_dag_file, cancel_transfer, cancel_vanity_search,
+    close_stream, create_identity, dial_local_node, download_file, export_keypair, fetch_dag,
+    generate_keypair, generate_peer_id, get_bootstrap_peers, get_identity, get_identity_error,
+    get_mesh_peers, get_peer_scores, get_pubsub_comparison, get_routing_table, import_identity,
+    import_keypair, inspect_peer_id, list_blocks, list_nodes, list_offers, list_remote_files,
+    list_streams, offer_file, open_stream, parse_multiaddr, remove_bootstrap_peer, remove_offer,
+    rpc_request, send_identify_command, send_node_command, send_ping_command, set_bootstrap_peers,
+    set_identity_passphrase, sign_message, start_identify_node, start_node, start_ping_node,
+    start_vanity_search, stop_accepting_streams, stop_node, unlock_identity, verify_signature,
+    write_stream, VanitySearch,
 };
+use keystore::Keystore;
+use node::NodeManager;
+use tauri::{Manager, RunEvent};
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -12,14 +27,72 @@ pub fn run() {
         .plugin(tauri_plugin_updater::Builder::new().build())
         .plugin(tauri_plugin_os::init())
         .plugin(tauri_plugin_opener::init())
+        .manage(VanitySearch::default())
+        .setup(|app| {
+            app.manage(NodeManager::new(app.path().app_data_dir()?));
+            let keystore = Keystore::open(&app.path().app_data_dir()?);
+            app.manage(keystore);
+            let bootstrap = BootstrapList::open(&app.path().app_data_dir()?);
+            app.manage(bootstrap);
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             generate_peer_id,
+            generate_keypair,
             start_ping_node,
             send_ping_command,
             parse_multiaddr,
             start_identify_node,
-            send_identify_command
+            send_identify_command,
+            start_node,
+            send_node_command,
+            stop_node,
+            list_nodes,
+            dial_local_node,
+            get_routing_table,
+            get_mesh_peers,
+            get_peer_scores,
+            get_pubsub_comparison,
+            rpc_request,
+            open_stream,
+            write_stream,
+            close_stream,
+            accept_streams,
+            stop_accepting_streams,
+            list_streams,
+            offer_file,
+            remove_offer,
+            list_offers,
+            list_remote_files,
+            download_file,
+            cancel_transfer,
+            add_dag_file,
+            fetch_dag,
+            list_blocks,
+            get_bootstrap_peers,
+            add_bootstrap_peer,
+            remove_bootstrap_peer,
+            set_bootstrap_peers,
+            get_identity,
+            get_identity_error,
+            create_identity,
+            unlock_identity,
+            set_identity_passphrase,
+            import_identity,
+            export_keypair,
+            import_keypair,
+            inspect_peer_id,
+            sign_message,
+            verify_signature,
+            start_vanity_search,
+            cancel_vanity_search
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            // 窗口全部关闭后应用退出，先优雅关闭所有节点
+            if let RunEvent::Exit = event {
+                tauri::async_runtime::block_on(app.state::<NodeManager>().stop_all());
+            }
+        });
 }
@@ -0,0 +1,79 @@
+use std::time::Duration;
+
+use libp2p::{
+    gossipsub, identify,
+    identity::Keypair,
+    kad::{self, store::MemoryStore},
+    mdns, ping, relay, rendezvous, request_response,
+    swarm::{self, behaviour::toggle::Toggle},
+};
+
+use super::{
+    dht,
+    floodsub::Floodsub,
+    pubsub::new_gossipsub,
+    relay::new_server as new_relay_server,
+    rendezvous::new_server,
+    rpc::{self, RpcCodec},
+    NodeConfig,
+};
+
+/// 协议版本，同时用作 identify 的 agent version
+pub const PROTOCOL_VERSION: &str = "/swarmbook/0.1.0";
+
+/// 共享节点的行为组合，各页面按需读取其中的事件
+#[derive(swarm::NetworkBehaviour)]
+pub struct NodeBehaviour {
+    pub ping: ping::Behaviour,
+    pub identify: identify::Behaviour,
+    pub kademlia: kad::Behaviour<MemoryStore>,
+    pub mdns: Toggle<mdns::tokio::Behaviour>,
+    pub rendezvous: rendezvous::client::Behaviour,
+    pub rendezvous_server: Toggle<rendezvous::server::Behaviour>,
+    pub gossipsub: gossipsub::Behaviour,
+    pub floodsub: Floodsub,
+    pub rpc: request_response::Behaviour<RpcCodec>,
+    /// 原始流，协议由前端在运行时指定
+    pub stream: libp2p_stream::Behaviour,
+    /// 中继服务端，配置了 `relay_server` 时启用
+    pub relay: Toggle<relay::Behaviour>,
+    /// 经中继监听和拨号，配合 swarm 中的中继传输使用
+    pub relay_client: relay::client::Behaviour,
+}
+
+impl NodeBehaviour {
+    pub fn new(
+        keypair: &Keypair,
+        relay_client: relay::client::Behaviour,
+        config: &NodeConfig,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let peer_id = keypair.public().to_peer_id();
+        let mdns = config
+            .mdns
+            .then(|| mdns::tokio::Behaviour::new(mdns::Config::default(), peer_id))
+            .transpose()?;
+        Ok(Self {
+            ping: ping::Behaviour::default(),
+            identify: identify::Behaviour::new(
+                identify::Config::new(PROTOCOL_VERSION.into(), keypair.public())
+                    .with_push_listen_addr_updates(true)
+                    .with_agent_version(PROTOCOL_VERSION.into())
+                    .with_interval(Duration::from_secs(3)),
+            ),
+            kademlia: dht::new_behaviour(peer_id),
+            mdns: mdns.into(),
+            rendezvous: rendezvous::client::Behaviour::new(keypair.clone()),
+            rendezvous_server: config.rendezvous_server.then(new_server).into(),
+            gossipsub: new_gossipsub(keypair, &config.gossipsub_score)?,
+            floodsub: Floodsub::new(peer_id),
+            rpc: rpc::new_behaviour(&config.rpc)?,
+            stream: libp2p_stream::Behaviour::new(),
+            relay: config
+                .relay_server
+                .as_ref()
+                .map(|relay| new_relay_server(peer_id, relay))
+                .into(),
+            relay_client,
+        })
+    }
+}
@@ -0,0 +1,723 @@
+use std::{
+    collections::VecDeque,
+    io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex as StdMutex,
+    },
+    time::Duration,
+};
+
+use anyhow::{anyhow, bail, Result};
+use cid::{multihash::Multihash, Cid};
+use libp2p::{
+    futures::{future, AsyncWriteExt, StreamExt},
+    PeerId, Stream, StreamProtocol,
+};
+use libp2p_stream::Control;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::{
+    fs,
+    io::{AsyncReadExt as _, AsyncWriteExt as _},
+    sync::broadcast,
+    task::JoinHandle,
+    time,
+};
+
+use super::{
+    files::unique_path,
+    streams::{read_frame, write_frame, Framing},
+    NodeEvent,
+};
+
+/// 块交换协议，一条流上可以依次发送多个请求
+pub const BLOCK_PROTOCOL: StreamProtocol = StreamProtocol::new("/swarmbook/bitswap/1.0.0");
+
+/// 叶子块大小
+const CHUNK_SIZE: usize = 256 * 1024;
+/// 每个 DAG 节点最多的链接数，与 UnixFS 的默认值相同
+const MAX_LINKS: usize = 174;
+/// 多编解码器表中的代码：叶子是原始数据，中间节点用 DAG-JSON 编码
+const RAW: u64 = 0x55;
+const DAG_JSON: u64 = 0x0129;
+const SHA2_256: u64 = 0x12;
+/// 打开流和等待单个应答的最长时间
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// DAG-JSON 中的链接写作 `{"/": "<cid>"}`
+#[derive(Serialize, Deserialize)]
+struct CidLink {
+    #[serde(rename = "/")]
+    cid: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Link {
+    cid: CidLink,
+    /// 子树包含的文件字节数
+    size: u64,
+}
+
+/// 文件 DAG 的中间节点，只有根节点带文件名。字段按 DAG-JSON 要求的键顺序排列
+#[derive(Serialize, Deserialize)]
+struct DagNode {
+    links: Vec<Link>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    size: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+enum Request {
+    WantHave { cids: Vec<String> },
+    WantBlock { cid: String },
+}
+
+/// `Block` 之后紧跟一帧块数据
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+enum Response {
+    #[serde(rename_all = "camelCase")]
+    Presence {
+        have: Vec<String>,
+        dont_have: Vec<String>,
+    },
+    Block {
+        cid: String,
+    },
+    DontHave {
+        cid: String,
+    },
+}
+
+/// 块交换事件，通过节点事件总线推送
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum BlockEvent {
+    #[serde(rename_all = "camelCase")]
+    Sent {
+        peer_id: String,
+        cid: String,
+        size: usize,
+    },
+    #[serde(rename_all = "camelCase")]
+    Received {
+        peer_id: String,
+        cid: String,
+        size: usize,
+    },
+    /// 对方发来的数据与 CID 不符，已丢弃并断开该流
+    #[serde(rename_all = "camelCase")]
+    Invalid { peer_id: String, cid: String },
+}
+
+/// 本地的一个块
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockInfo {
+    pub cid: String,
+    /// `raw` 或 `dag-json`
+    pub codec: &'static str,
+    pub size: usize,
+    /// DAG 节点的链接数，叶子为 0
+    pub links: usize,
+}
+
+/// 添加到块存储的文件
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddedDag {
+    /// 根节点的 CID
+    pub cid: String,
+    pub name: String,
+    pub size: u64,
+    /// DAG 中的块数，包括叶子和中间节点
+    pub blocks: usize,
+}
+
+/// 按 CID 取回的 DAG
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FetchedDag {
+    pub cid: String,
+    /// 根节点是文件 DAG 时的文件名
+    pub name: Option<String>,
+    pub size: u64,
+    pub blocks: usize,
+    /// 本次从网络取回的块数，其余已在本地
+    pub fetched: usize,
+    /// 指定了保存目录时写出的文件路径
+    pub path: Option<String>,
+}
+
+/// 临时文件编号，同一个块被并发写入时互不覆盖
+static NEXT_TMP: AtomicU64 = AtomicU64::new(0);
+
+/// 磁盘上的块存储，每个块是目录下以 CID 命名的文件。
+/// 节点重启后块仍在，已发布的 provider 记录可以继续应答；文件也不必整个放进内存
+#[derive(Clone)]
+struct Store {
+    dir: Arc<PathBuf>,
+}
+
+impl Store {
+    fn path(&self, cid: &Cid) -> PathBuf {
+        self.dir.join(cid.to_string())
+    }
+
+    async fn has(&self, cid: &Cid) -> bool {
+        fs::try_exists(self.path(cid)).await.unwrap_or(false)
+    }
+
+    /// 读取并校验块，内容与 CID 不符时视为没有并删除
+    async fn get(&self, cid: &Cid) -> Result<Option<Vec<u8>>> {
+        let path = self.path(cid);
+        let data = match fs::read(&path).await {
+            Ok(data) => data,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        if cid_of(cid.codec(), &data) != *cid {
+            tracing::warn!("block {} is corrupted on disk, removing it", cid);
+            let _ = fs::remove_file(&path).await;
+            return Ok(None);
+        }
+        Ok(Some(data))
+    }
+
+    /// 先写临时文件再改名，读到的块总是完整的
+    async fn put(&self, cid: &Cid, data: &[u8]) -> Result<()> {
+        let path = self.path(cid);
+        if fs::try_exists(&path).await? {
+            return Ok(());
+        }
+        let tmp = self.dir.join(format!(
+            "{}.{}.tmp",
+            cid,
+            NEXT_TMP.fetch_add(1, Ordering::Relaxed)
+        ));
+        let written = async {
+            let mut file = fs::File::create(&tmp).await?;
+            file.write_all(data).await?;
+            file.sync_all().await?;
+            fs::rename(&tmp, &path).await
+        }
+        .await;
+        if written.is_err() {
+            let _ = fs::remove_file(&tmp).await;
+        }
+        Ok(written?)
+    }
+
+    /// 所有块的 CID 和大小，忽略目录中的其他文件
+    async fn list(&self) -> Result<Vec<(Cid, u64)>> {
+        let mut blocks = Vec::new();
+        let mut entries = fs::read_dir(self.dir.as_path()).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let Some(cid) = entry
+                .file_name()
+                .to_str()
+                .and_then(|name| Cid::try_from(name).ok())
+            else {
+                continue;
+            };
+            blocks.push((cid, entry.metadata().await?.len()));
+        }
+        Ok(blocks)
+    }
+}
+
+/// 以 CID 为键的磁盘块存储，并在 [`BLOCK_PROTOCOL`] 上与其他节点交换块。
+/// 与原始流一样不经过事件循环，provider 记录由调用方通过 Kademlia 发布和查询
+#[derive(Clone)]
+pub struct Blocks {
+    control: Control,
+    events: broadcast::Sender<NodeEvent>,
+    store: Store,
+    server: Arc<JoinHandle<()>>,
+}
+
+impl Blocks {
+    /// 使用 `dir` 下已有的块，目录不存在时创建
+    pub async fn new(
+        mut control: Control,
+        events: broadcast::Sender<NodeEvent>,
+        dir: &Path,
+    ) -> Result<Self> {
+        fs::create_dir_all(dir)
+            .await
+            .map_err(|e| anyhow!("Cannot create block store {}: {}", dir.display(), e))?;
+        let store = Store {
+            dir: Arc::new(dir.to_path_buf()),
+        };
+        // 每个节点只创建一次，协议不可能已被注册
+        let mut incoming = control
+            .accept(BLOCK_PROTOCOL)
+            .expect("block protocol is registered once per node");
+        let server = {
+            let store = store.clone();
+            let events = events.clone();
+            tokio::spawn(async move {
+                while let Some((peer, stream)) = incoming.next().await {
+                    tokio::spawn(serve(store.clone(), events.clone(), peer, stream));
+                }
+            })
+        };
+        Ok(Self {
+            control,
+            events,
+            store,
+            server: Arc::new(server),
+        })
+    }
+
+    /// 把文件切成叶子块并逐层组成 Merkle DAG，返回根节点
+    pub async fn add_file(&self, path: &str) -> Result<AddedDag> {
+        let path = PathBuf::from(path.trim());
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .ok_or_else(|| anyhow!("{} has no file name", path.display()))?;
+        let mut file = fs::File::open(&path)
+            .await
+            .map_err(|e| anyhow!("Cannot read {}: {}", path.display(), e))?;
+
+        let mut level = Vec::new();
+        let mut size = 0;
+        loop {
+            let mut chunk = Vec::with_capacity(CHUNK_SIZE);
+            (&mut file)
+                .take(CHUNK_SIZE as u64)
+                .read_to_end(&mut chunk)
+                .await?;
+            // 空文件也有一个空的叶子
+            if chunk.is_empty() && !level.is_empty() {
+                break;
+            }
+            let len = chunk.len();
+            size += len as u64;
+            level.push((self.put(RAW, &chunk).await?, len as u64));
+            if len < CHUNK_SIZE {
+                break;
+            }
+        }
+
+        let mut blocks = level.len();
+        while level.len() > MAX_LINKS {
+            let mut parents = Vec::new();
+            for links in level.chunks(MAX_LINKS) {
+                parents.push(self.put_node(links, None).await?);
+            }
+            level = parents;
+            blocks += level.len();
+        }
+        let (cid, _) = self.put_node(&level, Some(name.clone())).await?;
+        Ok(AddedDag {
+            cid: cid.to_string(),
+            name,
+            size,
+            blocks: blocks + 1,
+        })
+    }
+
+    pub async fn has(&self, cid: &Cid) -> bool {
+        self.store.has(cid).await
+    }
+
+    pub async fn list(&self) -> Result<Vec<BlockInfo>> {
+        let mut blocks = Vec::new();
+        for (cid, size) in self.store.list().await? {
+            // 只有 DAG 节点需要读出内容数链接
+            let links = match cid.codec() {
+                DAG_JSON => self.store.get(&cid).await?.map_or(0, |data| {
+                    links(&cid, &data)
+                        .and_then(Result::ok)
+                        .map_or(0, |links| links.len())
+                }),
+                _ => 0,
+            };
+            blocks.push(BlockInfo {
+                cid: cid.to_string(),
+                codec: match cid.codec() {
+                    RAW => "raw",
+                    _ => "dag-json",
+                },
+                size: size as usize,
+                links,
+            });
+        }
+        blocks.sort_by(|a, b| a.cid.cmp(&b.cid));
+        Ok(blocks)
+    }
+
+    /// 向 `peers` 发送 want-have，返回回答拥有 `cid` 的节点
+    pub async fn who_has(&self, cid: Cid, peers: Vec<PeerId>) -> Vec<PeerId> {
+        let asks = peers.into_iter().map(|peer| async move {
+            let mut stream = self.open(peer).await.ok()?;
+            let request = Request::WantHave {
+                cids: vec![cid.to_string()],
+            };
+            let have = match request_response(&mut stream, &request).await.ok()? {
+                Response::Presence { have, .. } => have.contains(&cid.to_string()),
+                _ => false,
+            };
+            let _ = stream.close().await;
+            have.then_some(peer)
+        });
+        future::join_all(asks).await.into_iter().flatten().collect()
+    }
+
+    /// 逐层取回 `cid` 下缺少的块，各层的块分给 `peers` 并行请求。
+    /// 指定 `dest_dir` 时把文件 DAG 还原为文件
+    pub async fn fetch(
+        &self,
+        cid: Cid,
+        peers: Vec<PeerId>,
+        dest_dir: Option<&str>,
+    ) -> Result<FetchedDag> {
+        let mut sessions = Vec::new();
+        let mut blocks = 0;
+        let mut fetched = 0;
+        let mut level = vec![cid];
+        while !level.is_empty() {
+            let mut missing = Vec::new();
+            for cid in &level {
+                if !self.has(cid).await {
+                    missing.push(*cid);
+                }
+            }
+            if !missing.is_empty() {
+                if sessions.is_empty() {
+                    sessions = self.open_sessions(&peers).await;
+                    if sessions.is_empty() {
+                        bail!("No peer has block {}", missing[0]);
+                    }
+                }
+                fetched += missing.len();
+                self.fetch_level(missing, &mut sessions).await?;
+            }
+            blocks += level.len();
+
+            let mut next = Vec::new();
+            for cid in &level {
+                let data = self.store.get(cid).await?;
+                if let Some(links) = data.and_then(|data| links(cid, &data)) {
+                    next.extend(links?);
+                }
+            }
+            level = next;
+        }
+        for (_, stream) in &mut sessions {
+            let _ = stream.close().await;
+        }
+
+        let root = self
+            .store
+            .get(&cid)
+            .await?
+            .ok_or_else(|| anyhow!("Block {} is missing", cid))?;
+        let node = (cid.codec() == DAG_JSON)
+            .then(|| serde_json::from_slice::<DagNode>(&root))
+            .transpose()?;
+        let path = match dest_dir {
+            Some(dir) => Some(self.export(cid, node.as_ref(), dir).await?),
+            None => None,
+        };
+        Ok(FetchedDag {
+            cid: cid.to_string(),
+            size: node.as_ref().map_or(root.len() as u64, |node| node.size),
+            name: node.and_then(|node| node.name),
+            blocks,
+            fetched,
+            path,
+        })
+    }
+
+    /// 节点停止时结束服务
+    pub fn abort_all(&self) {
+        self.server.abort();
+    }
+
+    async fn put(&self, codec: u64, data: &[u8]) -> Result<Cid> {
+        let cid = cid_of(codec, data);
+        self.store.put(&cid, data).await?;
+        Ok(cid)
+    }
+
+    async fn put_node(&self, links: &[(Cid, u64)], name: Option<String>) -> Result<(Cid, u64)> {
+        let node = DagNode {
+            links: links
+                .iter()
+                .map(|(cid, size)| Link {
+                    cid: CidLink {
+                        cid: cid.to_string(),
+                    },
+                    size: *size,
+                })
+                .collect(),
+            name,
+            size: links.iter().map(|(_, size)| size).sum(),
+        };
+        let size = node.size;
+        Ok((self.put(DAG_JSON, &serde_json::to_vec(&node)?).await?, size))
+    }
+
+    async fn open(&self, peer: PeerId) -> Result<Stream> {
+        time::timeout(
+            REQUEST_TIMEOUT,
+            self.control.clone().open_stream(peer, BLOCK_PROTOCOL),
+        )
+        .await
+        .map_err(|_| anyhow!("Opening block stream to {} timed out", peer))?
+        .map_err(|e| anyhow!("Failed to open block stream to {}: {}", peer, e))
+    }
+
+    async fn open_sessions(&self, peers: &[PeerId]) -> Vec<(PeerId, Stream)> {
+        let opens = peers
+            .iter()
+            .map(|peer| async move { Some((*peer, self.open(*peer).await.ok()?)) });
+        future::join_all(opens)
+            .await
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
+    /// 各节点从同一个队列取块，出错的节点退出，剩下的节点接着处理。
+    /// 某个节点没有的块放回队列交给其他节点，所有节点都没有时才失败
+    async fn fetch_level(
+        &self,
+        cids: Vec<Cid>,
+        sessions: &mut Vec<(PeerId, Stream)>,
+    ) -> Result<()> {
+        // 待取的块和已回答没有它的节点
+        let mut pending: VecDeque<(Cid, Vec<PeerId>)> =
+            cids.into_iter().map(|cid| (cid, Vec::new())).collect();
+        while let Some((cid, _)) = pending.front() {
+            if sessions.is_empty() {
+                bail!("Lost all peers before fetching block {}", cid);
+            }
+            if let Some((cid, _)) = pending
+                .iter()
+                .find(|(_, refused)| sessions.iter().all(|(peer, _)| refused.contains(peer)))
+            {
+                bail!("No peer has block {}", cid);
+            }
+            // 一轮结束时仍有块留在队列，说明被放回时能接手的节点已经取完退出，再来一轮
+            let queue = StdMutex::new(pending);
+            let workers = sessions.iter_mut().map(|(peer, stream)| {
+                let queue = &queue;
+                async move {
+                    loop {
+                        let next = {
+                            let mut queue = queue.lock().unwrap_or_else(|e| e.into_inner());
+                            queue
+                                .iter()
+                                .position(|(_, refused)| !refused.contains(peer))
+                                .and_then(|at| queue.remove(at))
+                        };
+                        let Some((cid, mut refused)) = next else {
+                            return true;
+                        };
+                        match self.want_block(*peer, stream, cid).await {
+                            Ok(true) => {}
+                            Ok(false) => {
+                                refused.push(*peer);
+                                queue
+                                    .lock()
+                                    .unwrap_or_else(|e| e.into_inner())
+                                    .push_back((cid, refused));
+                            }
+                            Err(e) => {
+                                tracing::debug!("block session with {} failed: {e}", peer);
+                                queue
+                                    .lock()
+                                    .unwrap_or_else(|e| e.into_inner())
+                                    .push_front((cid, refused));
+                                return false;
+                            }
+                        }
+                    }
+                }
+            });
+            let alive = future::join_all(workers).await;
+            let mut alive = alive.into_iter();
+            sessions.retain(|_| alive.next().unwrap_or(false));
+            pending = queue.into_inner().unwrap_or_else(|e| e.into_inner());
+        }
+        Ok(())
+    }
+
+    /// 请求一个块，校验哈希后存入本地。对方没有时返回 `false`
+    async fn want_block(&self, peer: PeerId, stream: &mut Stream, cid: Cid) -> Result<bool> {
+        let request = Request::WantBlock {
+            cid: cid.to_string(),
+        };
+        match request_response(stream, &request).await? {
+            Response::Block { cid: sent } if sent == cid.to_string() => {}
+            Response::DontHave { .. } => return Ok(false),
+            _ => bail!("Unexpected response from {}", peer),
+        }
+        let data = time::timeout(
+            REQUEST_TIMEOUT,
+            read_frame(stream, Framing::LengthPrefixed, &mut []),
+        )
+        .await
+        .map_err(|_| anyhow!("Timed out waiting for block {}", cid))??
+        .ok_or_else(|| anyhow!("Stream closed before block {}", cid))?;
+        if cid_of(cid.codec(), &data) != cid {
+            let _ = self.events.send(NodeEvent::Block(BlockEvent::Invalid {
+                peer_id: peer.to_string(),
+                cid: cid.to_string(),
+            }));
+            bail!("Block {} from {} does not match its CID", cid, peer);
+        }
+        let _ = self.events.send(NodeEvent::Block(BlockEvent::Received {
+            peer_id: peer.to_string(),
+            cid: cid.to_string(),
+            size: data.len(),
+        }));
+        self.store.put(&cid, &data).await?;
+        Ok(true)
+    }
+
+    /// 按链接顺序写出叶子块，裸块直接写出
+    async fn export(&self, cid: Cid, node: Option<&DagNode>, dir: &str) -> Result<String> {
+        let dir = PathBuf::from(dir.trim());
+        if !fs::metadata(&dir).await.is_ok_and(|m| m.is_dir()) {
+            bail!("{} is not a directory", dir.display());
+        }
+        let name = node
+            .and_then(|node| node.name.clone())
+            .unwrap_or_else(|| cid.to_string());
+        let path = unique_path(&dir, &name).await?;
+        let mut file = fs::File::create(&path).await?;
+        let mut stack = vec![cid];
+        while let Some(cid) = stack.pop() {
+            let data = self
+                .store
+                .get(&cid)
+                .await?
+                .ok_or_else(|| anyhow!("Block {} is missing", cid))?;
+            match links(&cid, &data) {
+                Some(links) => stack.extend(links?.into_iter().rev()),
+                None => file.write_all(&data).await?,
+            }
+        }
+        file.flush().await?;
+        Ok(path.to_string_lossy().into_owned())
+    }
+}
+
+fn cid_of(codec: u64, data: &[u8]) -> Cid {
+    let hash = Multihash::wrap(SHA2_256, &Sha256::digest(data))
+        .expect("a SHA-256 digest fits in a multihash");
+    Cid::new_v1(codec, hash)
+}
+
+/// DAG 节点的子节点，叶子返回 `None`
+fn links(cid: &Cid, data: &[u8]) -> Option<Result<Vec<Cid>>> {
+    (cid.codec() == DAG_JSON).then(|| {
+        let node: DagNode = serde_json::from_slice(data)?;
+        node.links
+            .iter()
+            .map(|link| Ok(Cid::try_from(link.cid.cid.as_str())?))
+            .collect()
+    })
+}
+
+async fn request_response(stream: &mut Stream, request: &Request) -> Result<Response> {
+    write_frame(
+        stream,
+        Framing::LengthPrefixed,
+        &serde_json::to_vec(request)?,
+    )
+    .await?;
+    let frame = time::timeout(
+        REQUEST_TIMEOUT,
+        read_frame(stream, Framing::LengthPrefixed, &mut []),
+    )
+    .await
+    .map_err(|_| anyhow!("Timed out waiting for a response"))??
+    .ok_or_else(|| anyhow!("Stream closed before a response"))?;
+    Ok(serde_json::from_slice(&frame)?)
+}
+
+/// 依次应答同一条流上的请求，直到对方关闭
+async fn serve(
+    store: Store,
+    events: broadcast::Sender<NodeEvent>,
+    peer: PeerId,
+    mut stream: Stream,
+) {
+    loop {
+        let frame = match read_frame(&mut stream, Framing::LengthPrefixed, &mut []).await {
+            Ok(Some(frame)) => frame,
+            Ok(None) => break,
+            Err(e) => {
+                tracing::debug!("block stream from {} failed: {e}", peer);
+                return;
+            }
+        };
+        let Ok(request) = serde_json::from_slice::<Request>(&frame) else {
+            tracing::debug!("invalid block request from {}", peer);
+            return;
+        };
+        let result = match request {
+            Request::WantHave { cids } => {
+                let (mut have, mut dont_have) = (Vec::new(), Vec::new());
+                for cid in cids {
+                    match Cid::try_from(cid.as_str()) {
+                        Ok(parsed) if store.has(&parsed).await => have.push(cid),
+                        _ => dont_have.push(cid),
+                    }
+                }
+                send(&mut stream, &Response::Presence { have, dont_have }).await
+            }
+            Request::WantBlock { cid } => match lookup(&store, &cid).await {
+                Some(data) => {
+                    let size = data.len();
+                    let sent = async {
+                        send(&mut stream, &Response::Block { cid: cid.clone() }).await?;
+                        write_frame(&mut stream, Framing::LengthPrefixed, &data).await?;
+                        anyhow::Ok(())
+                    }
+                    .await;
+                    if sent.is_ok() {
+                        let _ = events.send(NodeEvent::Block(BlockEvent::Sent {
+                            peer_id: peer.to_string(),
+                            cid,
+                            size,
+                        }));
+                    }
+                    sent
+                }
+                None => send(&mut stream, &Response::DontHave { cid }).await,
+            },
+        };
+        if let Err(e) = result {
+            tracing::debug!("block stream to {} failed: {e}", peer);
+            return;
+        }
+    }
+    let _ = stream.close().await;
+}
+
+/// 读取对方请求的块，CID 无效或读取失败都按没有处理
+async fn lookup(store: &Store, cid: &str) -> Option<Vec<u8>> {
+    let cid = Cid::try_from(cid).ok()?;
+    store.get(&cid).await.ok().flatten()
+}
+
+async fn send(stream: &mut Stream, response: &Response) -> Result<()> {
+    write_frame(
+        stream,
+        Framing::LengthPrefixed,
+        &serde_json::to_vec(response)?,
+    )
+    .await?;
+    Ok(())
+}
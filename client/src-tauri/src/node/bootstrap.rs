@@ -0,0 +1,105 @@
+use std::{collections::HashMap, time::Duration};
+
+use libp2p::{Multiaddr, PeerId};
+use tokio::time::Instant;
+
+/// 首次拨号失败后的等待时间，之后每次失败翻倍
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+/// 重试间隔上限
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+struct BootstrapPeer {
+    addrs: Vec<Multiaddr>,
+    /// 本轮已拨号次数，连上后清零
+    attempts: u32,
+    /// 下次拨号时间，`None` 表示正在拨号或已连上
+    next_dial: Option<Instant>,
+    connected: bool,
+}
+
+/// 启动时拨号的引导节点，失败后按指数退避重试；连接全部断开后同样按退避重新拨号
+#[derive(Default)]
+pub struct Bootstrap {
+    peers: HashMap<PeerId, BootstrapPeer>,
+}
+
+impl Bootstrap {
+    /// 同一节点的多个地址合并为一次拨号，所有节点立即到期
+    pub fn new(addrs: Vec<(PeerId, Multiaddr)>) -> Self {
+        let now = Instant::now();
+        let mut peers: HashMap<PeerId, BootstrapPeer> = HashMap::new();
+        for (peer_id, addr) in addrs {
+            let peer = peers.entry(peer_id).or_insert_with(|| BootstrapPeer {
+                addrs: Vec::new(),
+                attempts: 0,
+                next_dial: Some(now),
+                connected: false,
+            });
+            if !peer.addrs.contains(&addr) {
+                peer.addrs.push(addr);
+            }
+        }
+        Self { peers }
+    }
+
+    pub fn addrs(&self) -> impl Iterator<Item = (PeerId, Multiaddr)> + '_ {
+        self.peers
+            .iter()
+            .flat_map(|(peer_id, peer)| peer.addrs.iter().map(|addr| (*peer_id, addr.clone())))
+    }
+
+    /// 最近一次需要拨号的时间
+    pub fn next_dial(&self) -> Option<Instant> {
+        self.peers.values().filter_map(|peer| peer.next_dial).min()
+    }
+
+    /// 取出到期的节点并记一次拨号，返回地址和这是第几次拨号
+    pub fn due(&mut self, now: Instant) -> Vec<(PeerId, Vec<Multiaddr>, u32)> {
+        self.peers
+            .iter_mut()
+            .filter(|(_, peer)| peer.next_dial.is_some_and(|at| at <= now))
+            .map(|(peer_id, peer)| {
+                peer.next_dial = None;
+                peer.attempts += 1;
+                (*peer_id, peer.addrs.clone(), peer.attempts)
+            })
+            .collect()
+    }
+
+    /// 拨号失败时安排下次重试，返回已拨号次数和等待时间。
+    /// 不是正在拨号的引导节点时返回 `None`
+    pub fn failed(&mut self, peer_id: &PeerId) -> Option<(u32, Duration)> {
+        let peer = self
+            .peers
+            .get_mut(peer_id)
+            .filter(|peer| !peer.connected && peer.next_dial.is_none())?;
+        let delay = backoff(peer.attempts);
+        peer.next_dial = Some(Instant::now() + delay);
+        Some((peer.attempts, delay))
+    }
+
+    pub fn connected(&mut self, peer_id: &PeerId) {
+        if let Some(peer) = self.peers.get_mut(peer_id) {
+            peer.connected = true;
+            peer.attempts = 0;
+            peer.next_dial = None;
+        }
+    }
+
+    /// 引导节点的最后一条连接断开时安排重新拨号，返回已拨号次数（为 0）和等待时间。
+    /// 不是已连上的引导节点时返回 `None`
+    pub fn disconnected(&mut self, peer_id: &PeerId) -> Option<(u32, Duration)> {
+        let peer = self.peers.get_mut(peer_id).filter(|peer| peer.connected)?;
+        peer.connected = false;
+        let delay = backoff(peer.attempts);
+        peer.next_dial = Some(Instant::now() + delay);
+        Some((peer.attempts, delay))
+    }
+}
+
+/// 第 `attempts` 次拨号失败后的等待时间
+fn backoff(attempts: u32) -> Duration {
+    INITIAL_BACKOFF
+        .saturating_mul(2u32.saturating_pow(attempts.saturating_sub(1)))
+        .min(MAX_BACKOFF)
+}
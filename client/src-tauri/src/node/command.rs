@@ -0,0 +1,221 @@
+use anyhow::Result;
+use libp2p::{Multiaddr, PeerId};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::oneshot;
+
+use super::{
+    ComparisonReport, ConnectionInfo, DiscoveredPeer, KBucketEntry, MessageValidator, PeerScore,
+    Responder, RpcReply, TopicMesh, ValidatorConfig,
+};
+
+/// 前端发给共享节点的命令
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "command")]
+pub enum NodeCommand {
+    Dial {
+        addr: Multiaddr,
+    },
+    /// 按 PeerId 拨号，`addrs` 为候选地址，只会建立一条连接
+    #[serde(rename_all = "camelCase")]
+    DialPeer {
+        peer_id: PeerId,
+        addrs: Vec<Multiaddr>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Disconnect {
+        peer_id: PeerId,
+    },
+    /// 新增监听地址，格式同 [`NodeConfig::listen_addrs`](super::NodeConfig)
+    ListenOn {
+        addr: String,
+    },
+    /// 关闭监听，`addr` 可以是请求的地址或实际监听到的地址
+    RemoveListener {
+        addr: String,
+    },
+    /// 切换是否自动拨号 mDNS 发现的节点
+    SetMdnsAutoDial {
+        enabled: bool,
+    },
+    /// 向路由表中的节点查询自身以填充 k-bucket
+    KadBootstrap,
+    #[serde(rename_all = "camelCase")]
+    KadGetClosestPeers {
+        peer_id: PeerId,
+    },
+    KadPutRecord {
+        key: String,
+        value: String,
+    },
+    KadGetRecord {
+        key: String,
+    },
+    KadStartProviding {
+        key: String,
+    },
+    KadGetProviders {
+        key: String,
+    },
+    /// 在 rendezvous 节点上注册自己，`ttl` 为秒，缺省由服务端决定
+    #[serde(rename_all = "camelCase")]
+    RendezvousRegister {
+        rendezvous_node: PeerId,
+        namespace: String,
+        ttl: Option<u64>,
+    },
+    /// 发现已注册的节点，不传 `namespace` 时返回所有命名空间。
+    /// 带上一次返回的 `cookie` 只会拿到新增的注册
+    #[serde(rename_all = "camelCase")]
+    RendezvousDiscover {
+        rendezvous_node: PeerId,
+        namespace: Option<String>,
+        limit: Option<u64>,
+        cookie: Option<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    RendezvousUnregister {
+        rendezvous_node: PeerId,
+        namespace: String,
+    },
+    GossipsubSubscribe {
+        topic: String,
+    },
+    GossipsubUnsubscribe {
+        topic: String,
+    },
+    /// 发布到话题，需要至少一个已连接的节点订阅了它
+    GossipsubPublish {
+        topic: String,
+        data: String,
+    },
+    /// 替换话题的校验器，按顺序执行，传空列表即取消校验
+    SetMessageValidators {
+        topic: String,
+        validators: Vec<ValidatorConfig>,
+    },
+    FloodsubSubscribe {
+        topic: String,
+    },
+    FloodsubUnsubscribe {
+        topic: String,
+    },
+    /// 发给所有已连接且订阅了该话题的节点，没有这样的节点时消息直接丢弃
+    FloodsubPublish {
+        topic: String,
+        data: String,
+    },
+    /// 同时通过 gossipsub 与 floodsub 订阅话题并开始统计，已开始时重新统计
+    PubsubCompareStart {
+        topic: String,
+    },
+    /// 每隔 `interval_ms` 把同一条约 `size` 字节的消息发到两个协议，共 `count` 条
+    #[serde(rename_all = "camelCase")]
+    PubsubComparePublish {
+        topic: String,
+        count: u32,
+        size: usize,
+        interval_ms: u64,
+    },
+    /// 停止统计，不会取消订阅
+    PubsubCompareStop {
+        topic: String,
+    },
+    /// 切换收到请求-响应协议请求时的应答方式
+    SetRpcResponder {
+        responder: Responder,
+    },
+}
+
+/// 命令执行结果，由事件循环通过 oneshot 回传给调用方
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum CommandReply {
+    /// 拨号已发起，连接结果通过 `connected` / `dialFailed` 事件推送
+    #[serde(rename_all = "camelCase")]
+    Dialing {
+        connection_id: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    Disconnected {
+        peer_id: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    Listening {
+        listener_id: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    ListenerRemoved {
+        listener_id: String,
+    },
+    MdnsAutoDial {
+        enabled: bool,
+    },
+    /// 请求已发给 rendezvous 节点，结果通过 `rendezvous` 事件推送
+    #[serde(rename_all = "camelCase")]
+    RendezvousRequested {
+        rendezvous_node: String,
+    },
+    /// DHT 查询已发起，进度通过 `kadQueryProgressed` 事件推送
+    #[serde(rename_all = "camelCase")]
+    Query {
+        query_id: String,
+    },
+    /// `changed` 为 false 表示此前已经订阅
+    Subscribed {
+        topic: String,
+        changed: bool,
+    },
+    Unsubscribed {
+        topic: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    Published {
+        message_id: String,
+    },
+    ValidatorsSet {
+        topic: String,
+        count: usize,
+    },
+    FloodsubPublished {
+        topic: String,
+    },
+    CompareStarted {
+        topic: String,
+    },
+    ComparePublishing {
+        topic: String,
+        count: u32,
+    },
+    CompareStopped {
+        topic: String,
+    },
+    RpcResponderSet {
+        responder: Responder,
+    },
+}
+
+/// 节点当前状态，用于给后挂载的页面补发事件
+#[derive(Debug, Default)]
+pub struct NodeSnapshot {
+    pub listen_addrs: Vec<Multiaddr>,
+    pub connections: Vec<ConnectionInfo>,
+    pub discovered: Vec<DiscoveredPeer>,
+}
+
+/// 事件循环接收的请求
+pub(crate) enum NodeRequest {
+    Command(NodeCommand, oneshot::Sender<Result<CommandReply>>),
+    Snapshot(oneshot::Sender<NodeSnapshot>),
+    RoutingTable(oneshot::Sender<Vec<KBucketEntry>>),
+    GossipsubMesh(oneshot::Sender<Vec<TopicMesh>>),
+    GossipsubScores(oneshot::Sender<Vec<PeerScore>>),
+    PubsubComparison(oneshot::Sender<Vec<ComparisonReport>>),
+    RpcRequest(PeerId, Value, oneshot::Sender<Result<RpcReply>>),
+    FindProviders(String, oneshot::Sender<Result<Vec<PeerId>>>),
+    AddValidator(
+        String,
+        Box<dyn MessageValidator>,
+        oneshot::Sender<Result<usize>>,
+    ),
+}
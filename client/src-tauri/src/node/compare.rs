@@ -0,0 +1,325 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use tokio::time::Instant;
+
+use super::{
+    pubsub::{MAX_TRANSMIT_SIZE, MESSAGE_OVERHEAD},
+    traffic::{ProtocolTraffic, TopicTraffic, Traffic, FLOODSUB, GOSSIPSUB},
+};
+
+/// 单次对比最多发布的消息数
+const MAX_COUNT: u32 = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PubsubProtocol {
+    Gossipsub,
+    Floodsub,
+}
+
+impl PubsubProtocol {
+    const ALL: [PubsubProtocol; 2] = [PubsubProtocol::Gossipsub, PubsubProtocol::Floodsub];
+
+    fn traffic_label(self) -> &'static str {
+        match self {
+            PubsubProtocol::Gossipsub => GOSSIPSUB,
+            PubsubProtocol::Floodsub => FLOODSUB,
+        }
+    }
+}
+
+/// 对比消息的负载，`pad` 用于把消息补到指定大小
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Probe {
+    seq: u32,
+    /// 发布时间（Unix 微秒），跨机器对比时依赖双方时钟同步
+    sent_at_us: u64,
+    #[serde(default)]
+    pad: String,
+}
+
+fn now_us() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_micros() as u64
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LatencyStats {
+    pub min: f64,
+    pub avg: f64,
+    pub p50: f64,
+    pub p95: f64,
+    pub max: f64,
+}
+
+impl LatencyStats {
+    fn from_us(latencies: &[u64]) -> Option<Self> {
+        let mut sorted = latencies.to_vec();
+        sorted.sort_unstable();
+        let ms = |us: u64| us as f64 / 1000.0;
+        let percentile = |p: f64| ms(sorted[((sorted.len() - 1) as f64 * p).round() as usize]);
+        Some(Self {
+            min: ms(*sorted.first()?),
+            avg: ms(sorted.iter().sum::<u64>()) / sorted.len() as f64,
+            p50: percentile(0.5),
+            p95: percentile(0.95),
+            max: ms(*sorted.last()?),
+        })
+    }
+}
+
+/// 某个协议在一次对比中的表现，均从本节点视角统计
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProtocolReport {
+    pub protocol: PubsubProtocol,
+    /// 本节点发布的对比消息数，`failed` 为其中发布失败的次数
+    pub sent: u32,
+    pub failed: u32,
+    /// 收到的不同对比消息数
+    pub received: u32,
+    /// 从发布到本节点收到的时间（毫秒）
+    pub latency_ms: Option<LatencyStats>,
+    /// 线上收到的该话题消息副本数，其中 `duplicates` 是协议内部去重时丢弃的
+    pub copies_in: u64,
+    pub duplicates: u64,
+    /// 该话题消息在线上收发的字节数，包括重复副本
+    pub message_bytes_in: u64,
+    pub message_bytes_out: u64,
+    /// 该协议所有子流的字节数，包括订阅、心跳和 IHAVE/IWANT 等控制消息，也包括其他话题
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComparisonReport {
+    pub topic: String,
+    pub elapsed_ms: u128,
+    /// 仍在发布中
+    pub publishing: bool,
+    pub protocols: Vec<ProtocolReport>,
+}
+
+struct Baseline {
+    protocol: ProtocolTraffic,
+    topic: TopicTraffic,
+}
+
+#[derive(Default)]
+struct Delivery {
+    sent: u32,
+    failed: u32,
+    received: HashMap<u32, u64>,
+}
+
+struct Publishing {
+    count: u32,
+    size: usize,
+    interval: Duration,
+    next_seq: u32,
+    next: Instant,
+}
+
+struct Session {
+    started: Instant,
+    baselines: HashMap<PubsubProtocol, Baseline>,
+    deliveries: HashMap<PubsubProtocol, Delivery>,
+    publishing: Option<Publishing>,
+}
+
+/// 同一个话题同时走 gossipsub 与 floodsub，对比两者的延迟、重复与带宽。
+/// 参与对比的节点都要先开始对比，此后收到的对比消息和流量计入统计
+pub struct Comparison {
+    traffic: Traffic,
+    sessions: HashMap<String, Session>,
+}
+
+impl Comparison {
+    pub fn new(traffic: Traffic) -> Self {
+        Self {
+            traffic,
+            sessions: HashMap::new(),
+        }
+    }
+
+    /// 开始或重新开始统计话题
+    pub fn start(&mut self, topic: &str) {
+        let baselines = PubsubProtocol::ALL
+            .into_iter()
+            .map(|protocol| {
+                let label = protocol.traffic_label();
+                let baseline = Baseline {
+                    protocol: self.traffic.protocol(label),
+                    topic: self.traffic.topic(label, topic),
+                };
+                (protocol, baseline)
+            })
+            .collect();
+        self.sessions.insert(
+            topic.to_string(),
+            Session {
+                started: Instant::now(),
+                baselines,
+                deliveries: HashMap::new(),
+                publishing: None,
+            },
+        );
+    }
+
+    pub fn stop(&mut self, topic: &str) -> bool {
+        self.sessions.remove(topic).is_some()
+    }
+
+    /// 按间隔发布 `count` 条约 `size` 字节的消息，每条同时发到两个协议。
+    /// `size` 包括探测消息自身的 JSON 字段，超过 gossipsub 单条上限时拒绝，
+    /// 否则只有 floodsub 能发出，两边的数据无法对比
+    pub fn publish(
+        &mut self,
+        topic: &str,
+        count: u32,
+        size: usize,
+        interval: Duration,
+    ) -> Result<()> {
+        if count == 0 || count > MAX_COUNT {
+            bail!("Count must be between 1 and {}", MAX_COUNT);
+        }
+        let max_size = MAX_TRANSMIT_SIZE.saturating_sub(MESSAGE_OVERHEAD + topic.len());
+        if size > max_size {
+            bail!("Size must be at most {} bytes on {}", max_size, topic);
+        }
+        let session = self
+            .sessions
+            .get_mut(topic)
+            .ok_or_else(|| anyhow!("Comparison on {} has not been started", topic))?;
+        if session.publishing.is_some() {
+            bail!("Comparison on {} is already publishing", topic);
+        }
+        session.publishing = Some(Publishing {
+            count,
+            size,
+            interval,
+            next_seq: 0,
+            next: Instant::now(),
+        });
+        Ok(())
+    }
+
+    pub fn next_send(&self) -> Option<Instant> {
+        self.sessions
+            .values()
+            .filter_map(|s| s.publishing.as_ref().map(|p| p.next))
+            .min()
+    }
+
+    /// 到期的消息：话题、负载以及是否是最后一条
+    pub fn due(&mut self, now: Instant) -> Vec<(String, Vec<u8>, bool)> {
+        let mut due = Vec::new();
+        for (topic, session) in self.sessions.iter_mut() {
+            let Some(publishing) = session.publishing.as_mut() else {
+                continue;
+            };
+            if publishing.next > now {
+                continue;
+            }
+            let mut probe = Probe {
+                seq: publishing.next_seq,
+                sent_at_us: now_us(),
+                pad: String::new(),
+            };
+            let len = serde_json::to_vec(&probe).map_or(0, |v| v.len());
+            probe.pad = "x".repeat(publishing.size.saturating_sub(len));
+            let payload = serde_json::to_vec(&probe).unwrap_or_default();
+
+            publishing.next_seq += 1;
+            publishing.next += publishing.interval;
+            let last = publishing.next_seq == publishing.count;
+            if last {
+                session.publishing = None;
+            }
+            due.push((topic.clone(), payload, last));
+        }
+        due
+    }
+
+    pub fn sent(&mut self, protocol: PubsubProtocol, topic: &str, ok: bool) {
+        if let Some(session) = self.sessions.get_mut(topic) {
+            let delivery = session.deliveries.entry(protocol).or_default();
+            delivery.sent += 1;
+            if !ok {
+                delivery.failed += 1;
+            }
+        }
+    }
+
+    /// 收到消息时调用，非对比消息直接忽略
+    pub fn received(&mut self, protocol: PubsubProtocol, topic: &str, data: &[u8]) {
+        let Some(session) = self.sessions.get_mut(topic) else {
+            return;
+        };
+        let Ok(probe) = serde_json::from_slice::<Probe>(data) else {
+            return;
+        };
+        session
+            .deliveries
+            .entry(protocol)
+            .or_default()
+            .received
+            .entry(probe.seq)
+            .or_insert_with(|| now_us().saturating_sub(probe.sent_at_us));
+    }
+
+    pub fn report(&self, topic: &str) -> Option<ComparisonReport> {
+        let session = self.sessions.get(topic)?;
+        let protocols = PubsubProtocol::ALL
+            .into_iter()
+            .map(|protocol| {
+                let label = protocol.traffic_label();
+                let baseline = &session.baselines[&protocol];
+                let traffic = self.traffic.protocol(label);
+                let topic_traffic = self.traffic.topic(label, topic);
+                let delivery = session.deliveries.get(&protocol);
+                let latencies: Vec<u64> = delivery
+                    .map(|d| d.received.values().copied().collect())
+                    .unwrap_or_default();
+                ProtocolReport {
+                    protocol,
+                    sent: delivery.map_or(0, |d| d.sent),
+                    failed: delivery.map_or(0, |d| d.failed),
+                    received: latencies.len() as u32,
+                    latency_ms: LatencyStats::from_us(&latencies),
+                    copies_in: topic_traffic.messages_in - baseline.topic.messages_in,
+                    duplicates: topic_traffic.duplicates_in - baseline.topic.duplicates_in,
+                    message_bytes_in: topic_traffic.bytes_in - baseline.topic.bytes_in,
+                    message_bytes_out: topic_traffic.bytes_out - baseline.topic.bytes_out,
+                    bytes_in: traffic.bytes_in - baseline.protocol.bytes_in,
+                    bytes_out: traffic.bytes_out - baseline.protocol.bytes_out,
+                }
+            })
+            .collect();
+        Some(ComparisonReport {
+            topic: topic.to_string(),
+            elapsed_ms: session.started.elapsed().as_millis(),
+            publishing: session.publishing.is_some(),
+            protocols,
+        })
+    }
+
+    pub fn reports(&self) -> Vec<ComparisonReport> {
+        let mut reports: Vec<_> = self
+            .sessions
+            .keys()
+            .filter_map(|topic| self.report(topic))
+            .collect();
+        reports.sort_by(|a, b| a.topic.cmp(&b.topic));
+        reports
+    }
+}
@@ -0,0 +1,47 @@
+use serde::Deserialize;
+
+use super::{RelayServerConfig, RpcConfig, ScoreConfig};
+
+/// 节点启动参数
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NodeConfig {
+    /// 监听地址，0 端口表示由系统自动分配
+    pub listen_addrs: Vec<String>,
+    /// 启动后自动拨号同进程内的其他节点
+    pub auto_dial: bool,
+    /// 通过 mDNS 发现局域网内的节点
+    pub mdns: bool,
+    /// 自动拨号 mDNS 发现的节点，运行时可通过命令切换
+    pub mdns_auto_dial: bool,
+    /// 作为 rendezvous 服务端接受其他节点注册
+    pub rendezvous_server: bool,
+    /// 启动时拨号并写入路由表的引导节点，不传时使用保存的引导列表
+    pub bootstrap_peers: Option<Vec<String>>,
+    /// gossipsub 节点评分参数与评分推送间隔
+    pub gossipsub_score: ScoreConfig,
+    /// 请求-响应协议名、编码与自动应答方式
+    pub rpc: RpcConfig,
+    /// 作为中继服务端为其他节点保留预约并转发连接，不传时不提供中继
+    pub relay_server: Option<RelayServerConfig>,
+}
+
+impl Default for NodeConfig {
+    fn default() -> Self {
+        Self {
+            listen_addrs: vec![
+                "/ip4/0.0.0.0/tcp/0".to_string(),
+                "/ip4/0.0.0.0/udp/0/quic-v1".to_string(),
+                "/ip4/0.0.0.0/tcp/0/ws".to_string(),
+            ],
+            auto_dial: false,
+            mdns: true,
+            mdns_auto_dial: false,
+            rendezvous_server: false,
+            bootstrap_peers: None,
+            gossipsub_score: ScoreConfig::default(),
+            rpc: RpcConfig::default(),
+            relay_server: None,
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use std::{collections::HashMap, time::SystemTime};
+
+use libp2p::{
+    kad::{
+        self, store::MemoryStore, GetProvidersOk, GetRecordOk, KBucketKey, NodeStatus, QueryResult,
+    },
+    PeerId, StreamProtocol,
+};
+use serde::Serialize;
+
+use super::NodeEvent;
+
+/// 本应用 DHT 的协议名，与公网 IPFS DHT 隔离
+pub const KAD_PROTOCOL: StreamProtocol = StreamProtocol::new("/swarmbook/kad/1.0.0");
+
+pub fn new_behaviour(peer_id: PeerId) -> kad::Behaviour<MemoryStore> {
+    let mut kademlia = kad::Behaviour::with_config(
+        peer_id,
+        MemoryStore::new(peer_id),
+        kad::Config::new(KAD_PROTOCOL),
+    );
+    // 本地实验节点没有确认过的外部地址，自动模式下会停留在 client，无法响应查询
+    kademlia.set_mode(Some(kad::Mode::Server));
+    kademlia
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KadPeer {
+    pub peer_id: String,
+    pub addrs: Vec<String>,
+}
+
+/// 查询的单步结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum KadQueryResult {
+    #[serde(rename_all = "camelCase")]
+    Bootstrap {
+        peer_id: String,
+        num_remaining: u32,
+    },
+    #[serde(rename_all = "camelCase")]
+    ClosestPeers {
+        key: String,
+        peers: Vec<KadPeer>,
+    },
+    #[serde(rename_all = "camelCase")]
+    FoundRecord {
+        key: String,
+        value: String,
+        publisher: Option<String>,
+        /// 返回记录的节点，为空表示来自本地存储
+        peer_id: Option<String>,
+    },
+    PutRecord {
+        key: String,
+    },
+    StartProviding {
+        key: String,
+    },
+    FoundProviders {
+        key: String,
+        providers: Vec<String>,
+    },
+    /// `get_record` / `get_providers` 没有更多结果
+    Finished,
+    Failed {
+        error: String,
+    },
+}
+
+/// k-bucket 中的一个节点
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KBucketEntry {
+    pub bucket: u32,
+    pub peer_id: String,
+    /// 与本地节点的 XOR 距离（十六进制）
+    pub distance: String,
+    pub addresses: Vec<String>,
+    pub connected: bool,
+    /// 最近一次收到该节点消息的 Unix 毫秒时间戳
+    pub last_seen_ms: Option<u128>,
+}
+
+fn key_to_string(key: &kad::RecordKey) -> String {
+    String::from_utf8_lossy(key.as_ref()).into_owned()
+}
+
+fn query_result(result: QueryResult) -> KadQueryResult {
+    let failed = |e: &dyn std::error::Error| KadQueryResult::Failed {
+        error: e.to_string(),
+    };
+    match result {
+        QueryResult::Bootstrap(Ok(ok)) => KadQueryResult::Bootstrap {
+            peer_id: ok.peer.to_string(),
+            num_remaining: ok.num_remaining,
+        },
+        QueryResult::GetClosestPeers(Ok(ok)) => KadQueryResult::ClosestPeers {
+            key: PeerId::from_bytes(&ok.key)
+                .map(|peer_id| peer_id.to_string())
+                .unwrap_or_else(|_| hex::encode(&ok.key)),
+            peers: ok
+                .peers
+                .into_iter()
+                .map(|peer| KadPeer {
+                    peer_id: peer.peer_id.to_string(),
+                    addrs: peer.addrs.iter().map(|a| a.to_string()).collect(),
+                })
+                .collect(),
+        },
+        QueryResult::GetRecord(Ok(GetRecordOk::FoundRecord(found))) => {
+            KadQueryResult::FoundRecord {
+                key: key_to_string(&found.record.key),
+                value: String::from_utf8_lossy(&found.record.value).into_owned(),
+                publisher: found.record.publisher.map(|p| p.to_string()),
+                peer_id: found.peer.map(|p| p.to_string()),
+            }
+        }
+        QueryResult::GetRecord(Ok(GetRecordOk::FinishedWithNoAdditionalRecord { .. }))
+        | QueryResult::GetProviders(Ok(GetProvidersOk::FinishedWithNoAdditionalRecord {
+            ..
+        })) => KadQueryResult::Finished,
+        QueryResult::PutRecord(Ok(ok)) | QueryResult::RepublishRecord(Ok(ok)) => {
+            KadQueryResult::PutRecord {
+                key: key_to_string(&ok.key),
+            }
+        }
+        QueryResult::StartProviding(Ok(ok)) | QueryResult::RepublishProvider(Ok(ok)) => {
+            KadQueryResult::StartProviding {
+                key: key_to_string(&ok.key),
+            }
+        }
+        QueryResult::GetProviders(Ok(GetProvidersOk::FoundProviders { key, providers })) => {
+            KadQueryResult::FoundProviders {
+                key: key_to_string(&key),
+                providers: providers.iter().map(|p| p.to_string()).collect(),
+            }
+        }
+        QueryResult::Bootstrap(Err(e)) => failed(&e),
+        QueryResult::GetClosestPeers(Err(e)) => failed(&e),
+        QueryResult::GetRecord(Err(e)) => failed(&e),
+        QueryResult::PutRecord(Err(e)) | QueryResult::RepublishRecord(Err(e)) => failed(&e),
+        QueryResult::StartProviding(Err(e)) | QueryResult::RepublishProvider(Err(e)) => failed(&e),
+        QueryResult::GetProviders(Err(e)) => failed(&e),
+    }
+}
+
+pub fn map_event(event: kad::Event) -> Option<NodeEvent> {
+    let event = match event {
+        kad::Event::OutboundQueryProgressed {
+            id, result, step, ..
+        } => NodeEvent::KadQueryProgressed {
+            query_id: id.to_string(),
+            step: step.count.get(),
+            last: step.last,
+            result: query_result(result),
+        },
+        kad::Event::RoutingUpdated {
+            peer,
+            is_new_peer,
+            addresses,
+            bucket_range,
+            old_peer,
+        } => NodeEvent::KadRoutingUpdated {
+            peer_id: peer.to_string(),
+            is_new_peer,
+            addresses: addresses.iter().map(|a| a.to_string()).collect(),
+            bucket: bucket_range.1.ilog2(),
+            evicted: old_peer.map(|p| p.to_string()),
+        },
+        kad::Event::ModeChanged { new_mode } => NodeEvent::KadModeChanged {
+            mode: new_mode.to_string(),
+        },
+        _ => return None,
+    };
+    Some(event)
+}
+
+/// 非空 k-bucket 中的全部节点
+pub fn routing_table(
+    kademlia: &mut kad::Behaviour<MemoryStore>,
+    local_peer_id: PeerId,
+    last_seen: &HashMap<PeerId, SystemTime>,
+) -> Vec<KBucketEntry> {
+    let local_key = KBucketKey::from(local_peer_id);
+    let mut entries = Vec::new();
+    for bucket in kademlia.kbuckets() {
+        let Some(index) = bucket.range().1.ilog2() else {
+            continue;
+        };
+        for entry in bucket.iter() {
+            let peer_id = *entry.node.key.preimage();
+            entries.push(KBucketEntry {
+                bucket: index,
+                peer_id: peer_id.to_string(),
+                distance: format!("{:x}", entry.node.key.distance(&local_key).0),
+                addresses: entry.node.value.iter().map(|a| a.to_string()).collect(),
+                connected: entry.status == NodeStatus::Connected,
+                last_seen_ms: last_seen
+                    .get(&peer_id)
+                    .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+                    .map(|d| d.as_millis()),
+            });
+        }
+    }
+    entries
+}
@@ -0,0 +1,203 @@
+use libp2p::{core::ConnectedPoint, multiaddr::Protocol, Multiaddr};
+use serde::Serialize;
+
+use super::{
+    BlockEvent, ComparisonReport, FileEvent, FloodsubEvent, GossipsubEvent, KadQueryResult,
+    RelayEvent, RendezvousEvent, RpcEvent, StreamEvent,
+};
+
+/// 根据地址判断连接使用的传输协议
+pub fn transport_of(addr: &Multiaddr) -> &'static str {
+    // 中继地址里也有中继节点自身的传输，需要先判断
+    if addr.iter().any(|p| matches!(p, Protocol::P2pCircuit)) {
+        "p2p-circuit"
+    } else if addr.iter().any(|p| matches!(p, Protocol::QuicV1)) {
+        "quic-v1"
+    } else if addr.iter().any(|p| matches!(p, Protocol::Wss(_))) {
+        "wss"
+    } else if addr.iter().any(|p| matches!(p, Protocol::Ws(_))) {
+        "ws"
+    } else if addr.iter().any(|p| matches!(p, Protocol::Tcp(_))) {
+        "tcp"
+    } else {
+        "unknown"
+    }
+}
+
+/// 节点生命周期
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeState {
+    Starting,
+    Running,
+    /// 正在关闭监听器和连接
+    Stopping,
+    Stopped,
+}
+
+/// 本地在连接中的角色
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConnectionRole {
+    /// 本地主动拨号
+    Dialer,
+    /// 对方拨入
+    Listener,
+}
+
+impl From<&ConnectedPoint> for ConnectionRole {
+    fn from(endpoint: &ConnectedPoint) -> Self {
+        if endpoint.is_dialer() {
+            ConnectionRole::Dialer
+        } else {
+            ConnectionRole::Listener
+        }
+    }
+}
+
+/// 已建立的连接
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionInfo {
+    pub peer_id: String,
+    pub connection_id: String,
+    /// 传输协议，如 `tcp`、`quic-v1`、`ws`
+    pub transport: String,
+    pub role: ConnectionRole,
+    pub remote_addr: String,
+    /// 与该节点已建立的连接数（含本连接）
+    pub num_established: u32,
+    /// 从开始拨号/接受到连接建立的耗时
+    pub established_in_ms: u128,
+}
+
+/// 已关闭的连接
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClosedConnection {
+    pub peer_id: String,
+    pub connection_id: String,
+    pub remote_addr: String,
+    /// 与该节点剩余的连接数
+    pub num_established: u32,
+    /// 为空表示本地主动关闭
+    pub cause: Option<String>,
+}
+
+/// 拨号失败
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DialFailure {
+    pub peer_id: Option<String>,
+    pub connection_id: String,
+    pub error: String,
+}
+
+/// mDNS 发现或过期的节点
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscoveredPeer {
+    pub peer_id: String,
+    pub addrs: Vec<String>,
+}
+
+/// 共享节点广播给所有页面的事件
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum NodeEvent {
+    StateChanged {
+        state: NodeState,
+    },
+    Listening {
+        addr: String,
+    },
+    /// 某个监听地址失效，例如网卡下线
+    ExpiredListenAddr {
+        addr: String,
+    },
+    /// 监听器关闭，`reason` 为空表示正常关闭
+    ListenerClosed {
+        addrs: Vec<String>,
+        reason: Option<String>,
+    },
+    ListenerError {
+        message: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    Dialing {
+        peer_id: Option<String>,
+        connection_id: String,
+    },
+    DialFailed(DialFailure),
+    /// 对方拨入但握手失败
+    #[serde(rename_all = "camelCase")]
+    IncomingConnectionFailed {
+        peer_id: Option<String>,
+        connection_id: String,
+        send_back_addr: String,
+        error: String,
+    },
+    Connected(ConnectionInfo),
+    Disconnected(ClosedConnection),
+    #[serde(rename_all = "camelCase")]
+    Ping {
+        peer_id: String,
+        rtt_ms: u128,
+    },
+    #[serde(rename_all = "camelCase")]
+    Identified {
+        peer_id: String,
+        protocol_version: String,
+        agent_version: String,
+        listen_addrs: Vec<String>,
+    },
+    MdnsDiscovered(DiscoveredPeer),
+    MdnsExpired(DiscoveredPeer),
+    /// 拨号引导节点，`attempt` 从 1 开始
+    #[serde(rename_all = "camelCase")]
+    BootstrapDialing {
+        peer_id: String,
+        attempt: u32,
+    },
+    /// 引导节点拨号失败或连接断开，`retry_in_ms` 后重试；断开时 `attempt` 为 0
+    #[serde(rename_all = "camelCase")]
+    BootstrapBackoff {
+        peer_id: String,
+        attempt: u32,
+        retry_in_ms: u128,
+    },
+    /// DHT 查询进度，同一查询可能有多步
+    #[serde(rename_all = "camelCase")]
+    KadQueryProgressed {
+        query_id: String,
+        step: usize,
+        last: bool,
+        result: KadQueryResult,
+    },
+    #[serde(rename_all = "camelCase")]
+    KadRoutingUpdated {
+        peer_id: String,
+        is_new_peer: bool,
+        addresses: Vec<String>,
+        bucket: Option<u32>,
+        /// 为腾出位置被移出路由表的节点
+        evicted: Option<String>,
+    },
+    KadModeChanged {
+        mode: String,
+    },
+    Rendezvous(RendezvousEvent),
+    Gossipsub(GossipsubEvent),
+    Floodsub(FloodsubEvent),
+    /// 对比消息已全部发出，附本节点此时的统计
+    ComparisonFinished(ComparisonReport),
+    Rpc(RpcEvent),
+    Stream(StreamEvent),
+    /// 向其他节点发送文件的情况，下载进度走下载命令的通道
+    File(FileEvent),
+    Block(BlockEvent),
+    Relay(RelayEvent),
+    Error {
+        message: String,
+    },
+}
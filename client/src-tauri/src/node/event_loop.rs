@@ -0,0 +1,1007 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, SystemTime},
+};
+
+use anyhow::{anyhow, bail, Result};
+use libp2p::{
+    core::{transport::ListenerId, ConnectedPoint},
+    futures::StreamExt,
+    gossipsub::{self, MessageAcceptance},
+    identify,
+    kad::{self, Quorum, Record, RecordKey},
+    mdns,
+    multiaddr::Protocol,
+    ping,
+    swarm::{
+        dial_opts::{DialOpts, PeerCondition},
+        ConnectionId, SwarmEvent,
+    },
+    Multiaddr, PeerId, Swarm,
+};
+use tokio::{
+    select,
+    sync::{broadcast, mpsc, oneshot, watch},
+    time::{self, Instant},
+};
+
+use crate::commands::validate_listen_addr;
+
+use super::{
+    bootstrap::Bootstrap, compare::Comparison, dht, floodsub, pubsub, relay, rendezvous, rpc::Rpc,
+    score::ScoreTracker, traffic::Traffic, transport_of, validation::MessageValidation,
+    ClosedConnection, CommandReply, ConnectionInfo, ConnectionRole, DialFailure, DiscoveredPeer,
+    GossipsubEvent, NodeBehaviour, NodeBehaviourEvent, NodeCommand, NodeEvent, NodeRequest,
+    NodeSnapshot, NodeState, PubsubProtocol, Responder, ScoreConfig,
+};
+
+/// 关闭时等待连接断开的最长时间
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// 持有 swarm 的后台任务，所有命令和事件都经过这里
+pub(crate) struct EventLoop {
+    swarm: Swarm<NodeBehaviour>,
+    requests: mpsc::Receiver<NodeRequest>,
+    events: broadcast::Sender<NodeEvent>,
+    state: watch::Sender<NodeState>,
+    /// 已建立的连接
+    connections: HashMap<ConnectionId, ConnectionInfo>,
+    /// 监听器：监听 ID -> 请求的地址
+    listeners: HashMap<ListenerId, Multiaddr>,
+    /// 监听器实际绑定的地址，例如 `0.0.0.0` 展开后的各网卡地址
+    bound_addrs: HashMap<ListenerId, Vec<Multiaddr>>,
+    /// 最近一次收到各节点消息的时间，用于路由表展示
+    last_seen: HashMap<PeerId, SystemTime>,
+    /// mDNS 发现且尚未过期的节点
+    discovered: HashMap<PeerId, Vec<Multiaddr>>,
+    mdns_auto_dial: bool,
+    bootstrap: Bootstrap,
+    /// 各话题的 gossipsub 消息校验
+    validation: MessageValidation,
+    /// gossipsub 评分分量
+    scores: ScoreTracker,
+    /// gossipsub 与 floodsub 对比
+    comparison: Comparison,
+    /// 请求-响应协议的应答方式与等待中的请求
+    rpc: Rpc,
+    /// 等待第一批 provider 的查询
+    provider_lookups: HashMap<kad::QueryId, oneshot::Sender<Result<Vec<PeerId>>>>,
+}
+
+impl EventLoop {
+    pub fn new(
+        swarm: Swarm<NodeBehaviour>,
+        requests: mpsc::Receiver<NodeRequest>,
+        events: broadcast::Sender<NodeEvent>,
+        state: watch::Sender<NodeState>,
+    ) -> Self {
+        Self {
+            swarm,
+            requests,
+            events,
+            state,
+            connections: HashMap::new(),
+            listeners: HashMap::new(),
+            bound_addrs: HashMap::new(),
+            last_seen: HashMap::new(),
+            discovered: HashMap::new(),
+            mdns_auto_dial: false,
+            bootstrap: Bootstrap::default(),
+            validation: MessageValidation::default(),
+            scores: ScoreTracker::new(ScoreConfig::default()),
+            comparison: Comparison::new(Traffic::default()),
+            rpc: Rpc::new(Responder::Echo),
+            provider_lookups: HashMap::new(),
+        }
+    }
+
+    pub fn set_mdns_auto_dial(&mut self, enabled: bool) {
+        self.mdns_auto_dial = enabled;
+    }
+
+    pub fn set_score_config(&mut self, config: ScoreConfig) {
+        self.scores = ScoreTracker::new(config);
+    }
+
+    /// 对比统计读取的流量，来自各连接的多路复用层
+    pub fn set_traffic(&mut self, traffic: Traffic) {
+        self.comparison = Comparison::new(traffic);
+    }
+
+    pub fn set_responder(&mut self, responder: Responder) {
+        self.rpc.set_responder(responder);
+    }
+
+    /// 引导节点先写入路由表，Kademlia 有了第一个节点后会自动发起 bootstrap 查询；
+    /// 拨号在事件循环启动后进行
+    pub fn set_bootstrap_peers(&mut self, peers: Vec<(PeerId, Multiaddr)>) {
+        self.bootstrap = Bootstrap::new(peers);
+        let addrs: Vec<_> = self.bootstrap.addrs().collect();
+        for (peer_id, addr) in addrs {
+            self.kademlia().add_address(&peer_id, addr);
+        }
+    }
+
+    pub fn listen_on(&mut self, addr: Multiaddr) -> Result<ListenerId> {
+        let listener_id = self.swarm.listen_on(addr.clone())?;
+        self.listeners.insert(listener_id, addr);
+        Ok(listener_id)
+    }
+
+    pub async fn run(mut self) {
+        self.set_state(NodeState::Running);
+        // 与 gossipsub 的评分衰减同步
+        let mut score_tick = time::interval(self.scores.config().decay_interval());
+        loop {
+            let next_dial = self.bootstrap.next_dial();
+            let next_probe = self.comparison.next_send();
+            let next_reply = self.rpc.next_reply();
+            select! {
+                request = self.requests.recv() => match request {
+                    Some(request) => self.handle_request(request),
+                    // 所有发送端都已释放，节点停止
+                    None => break,
+                },
+                event = self.swarm.select_next_some() => self.handle_swarm_event(event),
+                _ = time::sleep_until(next_dial.unwrap_or_else(Instant::now)), if next_dial.is_some() => {
+                    self.dial_bootstrap();
+                }
+                _ = time::sleep_until(next_probe.unwrap_or_else(Instant::now)), if next_probe.is_some() => {
+                    self.publish_probes();
+                }
+                _ = time::sleep_until(next_reply.unwrap_or_else(Instant::now)), if next_reply.is_some() => {
+                    self.rpc.send_due(&mut self.swarm.behaviour_mut().rpc);
+                }
+                _ = score_tick.tick() => self.refresh_scores(),
+            }
+        }
+        self.shutdown().await;
+    }
+
+    /// 关闭监听器并断开所有连接，等待对应事件推送出去
+    async fn shutdown(&mut self) {
+        self.set_state(NodeState::Stopping);
+        for listener_id in self.listeners.keys().copied().collect::<Vec<_>>() {
+            self.swarm.remove_listener(listener_id);
+        }
+        for peer_id in self.swarm.connected_peers().copied().collect::<Vec<_>>() {
+            let _ = self.swarm.disconnect_peer_id(peer_id);
+        }
+
+        let deadline = time::sleep(SHUTDOWN_TIMEOUT);
+        tokio::pin!(deadline);
+        while !self.connections.is_empty() || !self.listeners.is_empty() {
+            select! {
+                event = self.swarm.select_next_some() => self.handle_swarm_event(event),
+                _ = &mut deadline => {
+                    tracing::warn!("node shutdown timed out, dropping remaining connections");
+                    break;
+                }
+            }
+        }
+        self.set_state(NodeState::Stopped);
+    }
+
+    fn set_state(&mut self, state: NodeState) {
+        self.state.send_replace(state);
+        let _ = self.events.send(NodeEvent::StateChanged { state });
+    }
+
+    fn handle_swarm_event(&mut self, event: SwarmEvent<NodeBehaviourEvent>) {
+        match event {
+            SwarmEvent::Behaviour(NodeBehaviourEvent::Mdns(event)) => self.handle_mdns_event(event),
+            event => {
+                if let Some(event) = self.map_swarm_event(event) {
+                    // 没有页面订阅时发送会失败，忽略即可
+                    let _ = self.events.send(event);
+                }
+            }
+        }
+    }
+
+    /// mDNS 按地址上报，这里按节点合并后逐个推送
+    fn handle_mdns_event(&mut self, event: mdns::Event) {
+        let (discovered, list) = match event {
+            mdns::Event::Discovered(list) => (true, list),
+            mdns::Event::Expired(list) => (false, list),
+        };
+        let mut peers: HashMap<PeerId, Vec<Multiaddr>> = HashMap::new();
+        for (peer_id, addr) in list {
+            peers.entry(peer_id).or_default().push(addr);
+        }
+
+        for (peer_id, addrs) in peers {
+            let peer = DiscoveredPeer {
+                peer_id: peer_id.to_string(),
+                addrs: addrs.iter().map(|a| a.to_string()).collect(),
+            };
+            if discovered {
+                let known = self.discovered.entry(peer_id).or_default();
+                for addr in &addrs {
+                    if !known.contains(addr) {
+                        known.push(addr.clone());
+                    }
+                }
+                if self.mdns_auto_dial && !self.swarm.is_connected(&peer_id) {
+                    let opts = DialOpts::peer_id(peer_id)
+                        .addresses(addrs)
+                        .condition(PeerCondition::DisconnectedAndNotDialing)
+                        .build();
+                    // 正在拨号等情况会被条件拦下，不算错误
+                    let _ = self.swarm.dial(opts);
+                }
+                let _ = self.events.send(NodeEvent::MdnsDiscovered(peer));
+            } else {
+                if let Some(known) = self.discovered.get_mut(&peer_id) {
+                    known.retain(|a| !addrs.contains(a));
+                    if known.is_empty() {
+                        self.discovered.remove(&peer_id);
+                    }
+                }
+                let _ = self.events.send(NodeEvent::MdnsExpired(peer));
+            }
+        }
+    }
+
+    /// 拨号到期的引导节点，已经通过其他途径连上的直接标记完成
+    fn dial_bootstrap(&mut self) {
+        for (peer_id, addrs, attempt) in self.bootstrap.due(Instant::now()) {
+            if self.swarm.is_connected(&peer_id) {
+                self.bootstrap.connected(&peer_id);
+                continue;
+            }
+            let _ = self.events.send(NodeEvent::BootstrapDialing {
+                peer_id: peer_id.to_string(),
+                attempt,
+            });
+            if self
+                .dial(DialOpts::peer_id(peer_id).addresses(addrs).build())
+                .is_err()
+            {
+                self.bootstrap_failed(peer_id);
+            }
+        }
+    }
+
+    fn bootstrap_failed(&mut self, peer_id: PeerId) {
+        if let Some(retry) = self.bootstrap.failed(&peer_id) {
+            self.bootstrap_backoff(peer_id, retry);
+        }
+    }
+
+    fn bootstrap_backoff(&self, peer_id: PeerId, (attempt, delay): (u32, Duration)) {
+        let _ = self.events.send(NodeEvent::BootstrapBackoff {
+            peer_id: peer_id.to_string(),
+            attempt,
+            retry_in_ms: delay.as_millis(),
+        });
+    }
+
+    /// 同一条对比消息先后发到 gossipsub 与 floodsub
+    fn publish_probes(&mut self) {
+        for (topic, payload, last) in self.comparison.due(Instant::now()) {
+            let behaviour = self.swarm.behaviour_mut();
+            let published = behaviour
+                .gossipsub
+                .publish(gossipsub::IdentTopic::new(&topic), payload.clone());
+            behaviour
+                .floodsub
+                .publish_any(libp2p::floodsub::Topic::new(&topic), payload);
+            self.comparison
+                .sent(PubsubProtocol::Gossipsub, &topic, published.is_ok());
+            self.comparison.sent(PubsubProtocol::Floodsub, &topic, true);
+            if last {
+                if let Some(report) = self.comparison.report(&topic) {
+                    let _ = self.events.send(NodeEvent::ComparisonFinished(report));
+                }
+            }
+        }
+    }
+
+    fn refresh_scores(&mut self) {
+        let gossipsub = &self.swarm.behaviour().gossipsub;
+        self.scores.refresh(gossipsub);
+        if !self.scores.report_due() {
+            return;
+        }
+        let peers = self.scores.report(gossipsub);
+        if !peers.is_empty() {
+            let _ = self
+                .events
+                .send(NodeEvent::Gossipsub(GossipsubEvent::Scores { peers }));
+        }
+    }
+
+    fn handle_request(&mut self, request: NodeRequest) {
+        match request {
+            NodeRequest::Command(command, reply) => {
+                // 调用方可能已经放弃等待
+                let _ = reply.send(self.handle_command(command));
+            }
+            NodeRequest::Snapshot(reply) => {
+                let _ = reply.send(NodeSnapshot {
+                    listen_addrs: self.swarm.listeners().cloned().collect(),
+                    connections: self.connections.values().cloned().collect(),
+                    discovered: self
+                        .discovered
+                        .iter()
+                        .map(|(peer_id, addrs)| DiscoveredPeer {
+                            peer_id: peer_id.to_string(),
+                            addrs: addrs.iter().map(|a| a.to_string()).collect(),
+                        })
+                        .collect(),
+                });
+            }
+            NodeRequest::GossipsubMesh(reply) => {
+                let _ = reply.send(pubsub::topic_mesh(&self.swarm.behaviour().gossipsub));
+            }
+            NodeRequest::PubsubComparison(reply) => {
+                let _ = reply.send(self.comparison.reports());
+            }
+            NodeRequest::RpcRequest(peer_id, request, reply) => {
+                let request_id = self
+                    .swarm
+                    .behaviour_mut()
+                    .rpc
+                    .send_request(&peer_id, request);
+                self.rpc.sent(request_id, reply);
+            }
+            NodeRequest::FindProviders(key, reply) => {
+                let query_id = self.kademlia().get_providers(RecordKey::new(&key));
+                self.provider_lookups.insert(query_id, reply);
+            }
+            NodeRequest::GossipsubScores(reply) => {
+                let _ = reply.send(self.scores.report(&self.swarm.behaviour().gossipsub));
+            }
+            NodeRequest::AddValidator(topic, validator, reply) => {
+                let result = pubsub::topic(&topic)
+                    .map(|topic| self.validation.push(topic.hash(), validator));
+                let _ = reply.send(result);
+            }
+            NodeRequest::RoutingTable(reply) => {
+                let local_peer_id = *self.swarm.local_peer_id();
+                let _ = reply.send(dht::routing_table(
+                    &mut self.swarm.behaviour_mut().kademlia,
+                    local_peer_id,
+                    &self.last_seen,
+                ));
+            }
+        }
+    }
+
+    fn handle_command(&mut self, command: NodeCommand) -> Result<CommandReply> {
+        let reply = match command {
+            NodeCommand::Dial { addr } => self.dial(DialOpts::from(addr))?,
+            NodeCommand::DialPeer { peer_id, addrs } => self.dial(
+                DialOpts::peer_id(peer_id)
+                    .addresses(addrs)
+                    .condition(PeerCondition::DisconnectedAndNotDialing)
+                    .build(),
+            )?,
+            NodeCommand::Disconnect { peer_id } => {
+                self.swarm
+                    .disconnect_peer_id(peer_id)
+                    .map_err(|_| anyhow!("Not connected to {}", peer_id))?;
+                CommandReply::Disconnected {
+                    peer_id: peer_id.to_string(),
+                }
+            }
+            NodeCommand::ListenOn { addr } => {
+                let addr = validate_listen_addr(&addr).map_err(|e| anyhow!(e))?;
+                let listener_id = self.listen_on(self.with_relay_addr(addr)?)?;
+                CommandReply::Listening {
+                    listener_id: listener_id.to_string(),
+                }
+            }
+            NodeCommand::RemoveListener { addr } => {
+                let addr: Multiaddr = addr.trim().parse()?;
+                // 既可以传启动时请求的地址，也可以传实际监听到的地址
+                let listener_id = self
+                    .listeners
+                    .iter()
+                    .find(|(_, requested)| **requested == addr)
+                    .map(|(id, _)| *id)
+                    .or_else(|| self.listener_of(&addr))
+                    .ok_or_else(|| anyhow!("No listener on {}", addr))?;
+                self.swarm.remove_listener(listener_id);
+                CommandReply::ListenerRemoved {
+                    listener_id: listener_id.to_string(),
+                }
+            }
+            NodeCommand::SetMdnsAutoDial { enabled } => {
+                if enabled && !self.swarm.behaviour().mdns.is_enabled() {
+                    bail!("mDNS is disabled for this node");
+                }
+                self.mdns_auto_dial = enabled;
+                CommandReply::MdnsAutoDial { enabled }
+            }
+            NodeCommand::KadBootstrap => query(self.kademlia().bootstrap()?),
+            NodeCommand::KadGetClosestPeers { peer_id } => {
+                query(self.kademlia().get_closest_peers(peer_id))
+            }
+            NodeCommand::KadPutRecord { key, value } => {
+                let record = Record::new(RecordKey::new(&key), value.into_bytes());
+                query(self.kademlia().put_record(record, Quorum::One)?)
+            }
+            NodeCommand::KadGetRecord { key } => {
+                query(self.kademlia().get_record(RecordKey::new(&key)))
+            }
+            NodeCommand::KadStartProviding { key } => {
+                query(self.kademlia().start_providing(RecordKey::new(&key))?)
+            }
+            NodeCommand::KadGetProviders { key } => {
+                query(self.kademlia().get_providers(RecordKey::new(&key)))
+            }
+            NodeCommand::RendezvousRegister {
+                rendezvous_node,
+                namespace,
+                ttl,
+            } => {
+                self.ensure_external_addrs();
+                self.swarm.behaviour_mut().rendezvous.register(
+                    rendezvous::namespace(namespace)?,
+                    rendezvous_node,
+                    ttl,
+                )?;
+                rendezvous_requested(rendezvous_node)
+            }
+            NodeCommand::RendezvousDiscover {
+                rendezvous_node,
+                namespace,
+                limit,
+                cookie,
+            } => {
+                let namespace = namespace.map(rendezvous::namespace).transpose()?;
+                let cookie = cookie
+                    .as_deref()
+                    .map(rendezvous::decode_cookie)
+                    .transpose()?;
+                self.swarm.behaviour_mut().rendezvous.discover(
+                    namespace,
+                    cookie,
+                    limit,
+                    rendezvous_node,
+                );
+                rendezvous_requested(rendezvous_node)
+            }
+            NodeCommand::RendezvousUnregister {
+                rendezvous_node,
+                namespace,
+            } => {
+                self.swarm
+                    .behaviour_mut()
+                    .rendezvous
+                    .unregister(rendezvous::namespace(namespace)?, rendezvous_node);
+                rendezvous_requested(rendezvous_node)
+            }
+            NodeCommand::GossipsubSubscribe { topic } => {
+                let changed = pubsub::subscribe(
+                    &mut self.swarm.behaviour_mut().gossipsub,
+                    pubsub::topic(&topic)?,
+                    self.scores.config().topic_params(),
+                )?;
+                CommandReply::Subscribed {
+                    topic: topic.trim().to_string(),
+                    changed,
+                }
+            }
+            NodeCommand::GossipsubUnsubscribe { topic } => {
+                if !self
+                    .swarm
+                    .behaviour_mut()
+                    .gossipsub
+                    .unsubscribe(&pubsub::topic(&topic)?)
+                {
+                    bail!("Not subscribed to {}", topic);
+                }
+                CommandReply::Unsubscribed {
+                    topic: topic.trim().to_string(),
+                }
+            }
+            NodeCommand::GossipsubPublish { topic, data } => {
+                let message_id = self
+                    .swarm
+                    .behaviour_mut()
+                    .gossipsub
+                    .publish(pubsub::topic(&topic)?, data)
+                    .map_err(|e| pubsub::publish_error(&topic, e))?;
+                CommandReply::Published {
+                    message_id: pubsub::format_message_id(&message_id),
+                }
+            }
+            NodeCommand::SetMessageValidators { topic, validators } => {
+                let count = self
+                    .validation
+                    .set(pubsub::topic(&topic)?.hash(), validators)?;
+                CommandReply::ValidatorsSet {
+                    topic: topic.trim().to_string(),
+                    count,
+                }
+            }
+            NodeCommand::FloodsubSubscribe { topic } => {
+                let changed = self
+                    .swarm
+                    .behaviour_mut()
+                    .floodsub
+                    .subscribe(floodsub::topic(&topic)?);
+                CommandReply::Subscribed {
+                    topic: topic.trim().to_string(),
+                    changed,
+                }
+            }
+            NodeCommand::FloodsubUnsubscribe { topic } => {
+                if !self
+                    .swarm
+                    .behaviour_mut()
+                    .floodsub
+                    .unsubscribe(floodsub::topic(&topic)?)
+                {
+                    bail!("Not subscribed to {}", topic);
+                }
+                CommandReply::Unsubscribed {
+                    topic: topic.trim().to_string(),
+                }
+            }
+            NodeCommand::FloodsubPublish { topic, data } => {
+                self.swarm
+                    .behaviour_mut()
+                    .floodsub
+                    .publish_any(floodsub::topic(&topic)?, data);
+                CommandReply::FloodsubPublished {
+                    topic: topic.trim().to_string(),
+                }
+            }
+            NodeCommand::PubsubCompareStart { topic } => {
+                let gossipsub_topic = pubsub::topic(&topic)?;
+                let topic = gossipsub_topic.hash().into_string();
+                let params = self.scores.config().topic_params();
+                let behaviour = self.swarm.behaviour_mut();
+                pubsub::subscribe(&mut behaviour.gossipsub, gossipsub_topic, params)?;
+                behaviour.floodsub.subscribe(floodsub::topic(&topic)?);
+                self.comparison.start(&topic);
+                CommandReply::CompareStarted { topic }
+            }
+            NodeCommand::PubsubComparePublish {
+                topic,
+                count,
+                size,
+                interval_ms,
+            } => {
+                let topic = pubsub::topic(&topic)?.hash().into_string();
+                self.comparison
+                    .publish(&topic, count, size, Duration::from_millis(interval_ms))?;
+                CommandReply::ComparePublishing { topic, count }
+            }
+            NodeCommand::PubsubCompareStop { topic } => {
+                let topic = pubsub::topic(&topic)?.hash().into_string();
+                if !self.comparison.stop(&topic) {
+                    bail!("Comparison on {} has not been started", topic);
+                }
+                CommandReply::CompareStopped { topic }
+            }
+            NodeCommand::SetRpcResponder { responder } => {
+                self.rpc.set_responder(responder.clone());
+                CommandReply::RpcResponderSet { responder }
+            }
+        };
+        Ok(reply)
+    }
+
+    /// 注册需要外部地址。没有 AutoNAT 等确认机制时，把当前监听地址当作外部地址，
+    /// 跨子网时对方能否拨通取决于路由。回环地址对其他机器没有意义，不会加入；
+    /// 监听地址失效后对应的外部地址也会移除
+    fn ensure_external_addrs(&mut self) {
+        if self.swarm.external_addresses().next().is_some() {
+            return;
+        }
+        let addrs: Vec<_> = self
+            .swarm
+            .listeners()
+            .filter(|addr| is_publishable(addr))
+            .cloned()
+            .collect();
+        for addr in addrs {
+            self.swarm.add_external_address(addr);
+        }
+    }
+
+    /// `/p2p/<中继>/p2p-circuit` 省略了中继地址时，用与中继已有连接的地址补全
+    fn with_relay_addr(&self, addr: Multiaddr) -> Result<Multiaddr> {
+        let Some(Protocol::P2p(relay)) = addr.iter().next() else {
+            return Ok(addr);
+        };
+        let relay_id = relay.to_string();
+        let relay_addr = self
+            .connections
+            .values()
+            .filter(|c| c.peer_id == relay_id && c.transport != "p2p-circuit")
+            // 对方拨入时的远端地址是临时端口，优先用本地拨出的连接
+            .min_by_key(|c| !matches!(c.role, ConnectionRole::Dialer))
+            .map(|c| c.remote_addr.parse::<Multiaddr>())
+            .transpose()?
+            .map(|addr| {
+                addr.into_iter()
+                    .filter(|p| !matches!(p, Protocol::P2p(_)))
+                    .collect::<Multiaddr>()
+            })
+            .ok_or_else(|| {
+                anyhow!(
+                    "Not connected to relay {}, include its address before /p2p",
+                    relay
+                )
+            })?;
+        Ok(relay_addr.into_iter().chain(addr.iter()).collect())
+    }
+
+    fn kademlia(&mut self) -> &mut kad::Behaviour<kad::store::MemoryStore> {
+        &mut self.swarm.behaviour_mut().kademlia
+    }
+
+    fn dial(&mut self, opts: DialOpts) -> Result<CommandReply> {
+        let connection_id = opts.connection_id();
+        // 同步失败（如地址不受支持）不会产生 OutgoingConnectionError，这里补发
+        if let Err(e) = self.swarm.dial(opts) {
+            let _ = self.events.send(NodeEvent::DialFailed(DialFailure {
+                peer_id: None,
+                connection_id: connection_id.to_string(),
+                error: e.to_string(),
+            }));
+            return Err(e.into());
+        }
+        Ok(CommandReply::Dialing {
+            connection_id: connection_id.to_string(),
+        })
+    }
+
+    /// 把查到的 provider 交给等待的调用方。provider 的地址只在查询进行期间可用，
+    /// 所以趁现在拨号，之后打开流时连接已在建立中
+    fn providers_found(&mut self, query_id: kad::QueryId, result: &kad::GetProvidersResult) {
+        let Some(reply) = self.provider_lookups.remove(&query_id) else {
+            return;
+        };
+        let providers = match result {
+            Ok(kad::GetProvidersOk::FoundProviders { providers, .. }) => {
+                let local_peer_id = *self.swarm.local_peer_id();
+                let providers: Vec<_> = providers
+                    .iter()
+                    .copied()
+                    .filter(|peer_id| *peer_id != local_peer_id)
+                    .collect();
+                if providers.is_empty() {
+                    // 只找到本节点自己，继续等后续结果
+                    self.provider_lookups.insert(query_id, reply);
+                    return;
+                }
+                for peer_id in &providers {
+                    let _ = self.dial(
+                        DialOpts::peer_id(*peer_id)
+                            .condition(PeerCondition::DisconnectedAndNotDialing)
+                            .build(),
+                    );
+                }
+                Ok(providers)
+            }
+            Ok(kad::GetProvidersOk::FinishedWithNoAdditionalRecord { .. }) => Ok(Vec::new()),
+            Err(e) => Err(anyhow!("Provider lookup failed: {}", e)),
+        };
+        let _ = reply.send(providers);
+    }
+
+    /// 根据实际监听地址找到对应的监听器
+    fn listener_of(&self, addr: &Multiaddr) -> Option<ListenerId> {
+        self.bound_addrs
+            .iter()
+            .find(|(_, addrs)| addrs.contains(addr))
+            .map(|(id, _)| *id)
+    }
+
+    fn map_swarm_event(&mut self, event: SwarmEvent<NodeBehaviourEvent>) -> Option<NodeEvent> {
+        let event = match event {
+            SwarmEvent::NewListenAddr {
+                listener_id,
+                address,
+            } => {
+                let addr = address.to_string();
+                // 中继服务端在预约应答里告诉客户端自己的外部地址，
+                // 没有 AutoNAT 等确认机制时直接使用监听地址；回环地址对远端客户端不可达
+                if self.swarm.behaviour().relay.is_enabled() && is_publishable(&address) {
+                    self.swarm.add_external_address(address.clone());
+                }
+                self.bound_addrs
+                    .entry(listener_id)
+                    .or_default()
+                    .push(address);
+                NodeEvent::Listening { addr }
+            }
+            SwarmEvent::ExpiredListenAddr {
+                listener_id,
+                address,
+            } => {
+                if let Some(addrs) = self.bound_addrs.get_mut(&listener_id) {
+                    addrs.retain(|a| *a != address);
+                }
+                self.swarm.remove_external_address(&address);
+                NodeEvent::ExpiredListenAddr {
+                    addr: address.to_string(),
+                }
+            }
+            SwarmEvent::ListenerClosed {
+                listener_id,
+                addresses,
+                reason,
+            } => {
+                let requested = self.listeners.remove(&listener_id);
+                self.bound_addrs.remove(&listener_id);
+                for addr in &addresses {
+                    self.swarm.remove_external_address(addr);
+                }
+                if let (Some(relay_peer_id), Err(e)) =
+                    (requested.as_ref().and_then(relay::circuit_relay), &reason)
+                {
+                    let _ =
+                        self.events
+                            .send(NodeEvent::Relay(relay::RelayEvent::ReservationFailed {
+                                relay_peer_id: relay_peer_id.to_string(),
+                                error: e.to_string(),
+                            }));
+                }
+                NodeEvent::ListenerClosed {
+                    addrs: addresses.iter().map(|a| a.to_string()).collect(),
+                    reason: reason.err().map(|e| e.to_string()),
+                }
+            }
+            SwarmEvent::ListenerError { error, .. } => NodeEvent::ListenerError {
+                message: error.to_string(),
+            },
+            SwarmEvent::Dialing {
+                peer_id,
+                connection_id,
+            } => NodeEvent::Dialing {
+                peer_id: peer_id.map(|p| p.to_string()),
+                connection_id: connection_id.to_string(),
+            },
+            SwarmEvent::OutgoingConnectionError {
+                connection_id,
+                peer_id,
+                error,
+            } => {
+                let _ = self.events.send(NodeEvent::DialFailed(DialFailure {
+                    peer_id: peer_id.map(|p| p.to_string()),
+                    connection_id: connection_id.to_string(),
+                    error: error.to_string(),
+                }));
+                // 先推送失败原因，再推送重试安排
+                if let Some(peer_id) = peer_id {
+                    self.bootstrap_failed(peer_id);
+                }
+                return None;
+            }
+            SwarmEvent::IncomingConnectionError {
+                connection_id,
+                send_back_addr,
+                error,
+                peer_id,
+                ..
+            } => NodeEvent::IncomingConnectionFailed {
+                peer_id: peer_id.map(|p| p.to_string()),
+                connection_id: connection_id.to_string(),
+                send_back_addr: send_back_addr.to_string(),
+                error: error.to_string(),
+            },
+            SwarmEvent::ConnectionEstablished {
+                peer_id,
+                connection_id,
+                endpoint,
+                num_established,
+                established_in,
+                ..
+            } => {
+                let remote_addr = endpoint.get_remote_address();
+                // 经中继拨入的连接远端地址只有 `/p2p/<对方>`，传输要看本地的中继监听地址
+                let transport = match &endpoint {
+                    ConnectedPoint::Listener { local_addr, .. }
+                        if relay::circuit_relay(local_addr).is_some() =>
+                    {
+                        transport_of(local_addr)
+                    }
+                    _ => transport_of(remote_addr),
+                };
+                let info = ConnectionInfo {
+                    peer_id: peer_id.to_string(),
+                    connection_id: connection_id.to_string(),
+                    transport: transport.to_string(),
+                    role: (&endpoint).into(),
+                    remote_addr: remote_addr.to_string(),
+                    num_established: num_established.get(),
+                    established_in_ms: established_in.as_millis(),
+                };
+                self.connections.insert(connection_id, info.clone());
+                self.scores
+                    .connection_established(connection_id, peer_id, remote_addr);
+                if num_established.get() == 1 {
+                    self.swarm
+                        .behaviour_mut()
+                        .floodsub
+                        .add_node_to_partial_view(peer_id);
+                }
+                self.bootstrap.connected(&peer_id);
+                self.last_seen.insert(peer_id, SystemTime::now());
+                NodeEvent::Connected(info)
+            }
+            SwarmEvent::ConnectionClosed {
+                peer_id,
+                connection_id,
+                endpoint,
+                num_established,
+                cause,
+            } => {
+                self.connections.remove(&connection_id);
+                self.scores.connection_closed(connection_id);
+                if num_established == 0 {
+                    self.swarm
+                        .behaviour_mut()
+                        .floodsub
+                        .remove_node_from_partial_view(&peer_id);
+                    if let Some(retry) = self.bootstrap.disconnected(&peer_id) {
+                        self.bootstrap_backoff(peer_id, retry);
+                    }
+                }
+                NodeEvent::Disconnected(ClosedConnection {
+                    peer_id: peer_id.to_string(),
+                    connection_id: connection_id.to_string(),
+                    remote_addr: endpoint.get_remote_address().to_string(),
+                    num_established,
+                    cause: cause.map(|e| e.to_string()),
+                })
+            }
+            SwarmEvent::Behaviour(NodeBehaviourEvent::Ping(ping::Event {
+                peer, result, ..
+            })) => match result {
+                Ok(rtt) => {
+                    self.last_seen.insert(peer, SystemTime::now());
+                    NodeEvent::Ping {
+                        peer_id: peer.to_string(),
+                        rtt_ms: rtt.as_millis(),
+                    }
+                }
+                Err(e) => NodeEvent::Error {
+                    message: e.to_string(),
+                },
+            },
+            SwarmEvent::Behaviour(NodeBehaviourEvent::Identify(identify::Event::Received {
+                peer_id,
+                info,
+                ..
+            })) => {
+                self.last_seen.insert(peer_id, SystemTime::now());
+                // 对方支持同一个 DHT 协议时，把它的监听地址加入路由表
+                if info.protocols.contains(&dht::KAD_PROTOCOL) {
+                    for addr in &info.listen_addrs {
+                        self.kademlia().add_address(&peer_id, addr.clone());
+                    }
+                }
+                NodeEvent::Identified {
+                    peer_id: peer_id.to_string(),
+                    protocol_version: info.protocol_version,
+                    agent_version: info.agent_version,
+                    listen_addrs: info.listen_addrs.iter().map(|a| a.to_string()).collect(),
+                }
+            }
+            SwarmEvent::Behaviour(NodeBehaviourEvent::Rendezvous(event)) => {
+                rendezvous::map_client_event(event)
+            }
+            SwarmEvent::Behaviour(NodeBehaviourEvent::RendezvousServer(event)) => {
+                rendezvous::map_server_event(event)
+            }
+            SwarmEvent::Behaviour(NodeBehaviourEvent::Relay(event)) => {
+                return relay::map_server_event(event);
+            }
+            SwarmEvent::Behaviour(NodeBehaviourEvent::RelayClient(event)) => {
+                relay::map_client_event(event)
+            }
+            SwarmEvent::Behaviour(NodeBehaviourEvent::Rpc(event)) => self
+                .rpc
+                .handle_event(&mut self.swarm.behaviour_mut().rpc, event),
+            SwarmEvent::Behaviour(NodeBehaviourEvent::Floodsub(event)) => {
+                if let libp2p::floodsub::Event::Message(message) = &event {
+                    for topic in &message.topics {
+                        self.comparison.received(
+                            PubsubProtocol::Floodsub,
+                            topic.id(),
+                            &message.data,
+                        );
+                    }
+                }
+                floodsub::map_event(event)
+            }
+            SwarmEvent::Behaviour(NodeBehaviourEvent::Gossipsub(event)) => {
+                if let gossipsub::Event::Message {
+                    propagation_source,
+                    message_id,
+                    message,
+                } = &event
+                {
+                    // 校验结论决定是否转发，拒绝时扣转发者的分
+                    let rejected = self.validation.validate(propagation_source, message);
+                    let acceptance = rejected
+                        .as_ref()
+                        .map_or(MessageAcceptance::Accept, |(_, verdict)| {
+                            verdict.acceptance()
+                        });
+                    match &acceptance {
+                        MessageAcceptance::Accept => {
+                            self.scores.delivered(*propagation_source, &message.topic);
+                            self.comparison.received(
+                                PubsubProtocol::Gossipsub,
+                                message.topic.as_str(),
+                                &message.data,
+                            );
+                        }
+                        MessageAcceptance::Reject => {
+                            self.scores.rejected(*propagation_source, &message.topic)
+                        }
+                        MessageAcceptance::Ignore => {}
+                    }
+                    self.swarm
+                        .behaviour_mut()
+                        .gossipsub
+                        .report_message_validation_result(
+                            message_id,
+                            propagation_source,
+                            acceptance,
+                        );
+                    if let Some((validator, verdict)) = rejected {
+                        let (acceptance, reason) = verdict.rejection()?;
+                        return Some(pubsub::invalid_event(
+                            propagation_source,
+                            message_id,
+                            message,
+                            validator,
+                            acceptance,
+                            reason,
+                        ));
+                    }
+                }
+                pubsub::map_event(event)
+            }
+            SwarmEvent::Behaviour(NodeBehaviourEvent::Kademlia(event)) => {
+                match &event {
+                    kad::Event::RoutingUpdated { peer, .. } => {
+                        self.last_seen.insert(*peer, SystemTime::now());
+                    }
+                    kad::Event::OutboundQueryProgressed {
+                        id,
+                        result: kad::QueryResult::GetProviders(result),
+                        ..
+                    } => self.providers_found(*id, result),
+                    _ => {}
+                }
+                return dht::map_event(event);
+            }
+            _ => return None,
+        };
+        Some(event)
+    }
+}
+
+/// 能告诉其他节点的地址：排除回环和未指定地址，以及经中继的电路地址
+fn is_publishable(addr: &Multiaddr) -> bool {
+    addr.iter().all(|p| match p {
+        Protocol::Ip4(ip) => !ip.is_loopback() && !ip.is_unspecified(),
+        Protocol::Ip6(ip) => !ip.is_loopback() && !ip.is_unspecified(),
+        Protocol::P2pCircuit => false,
+        _ => true,
+    })
+}
+
+fn query(query_id: kad::QueryId) -> CommandReply {
+    CommandReply::Query {
+        query_id: query_id.to_string(),
+    }
+}
+
+fn rendezvous_requested(rendezvous_node: PeerId) -> CommandReply {
+    CommandReply::RendezvousRequested {
+        rendezvous_node: rendezvous_node.to_string(),
+    }
+}
@@ -0,0 +1,681 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::{self, Read},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex as StdMutex},
+    time::Duration,
+};
+
+use anyhow::{anyhow, bail, Result};
+use libp2p::{
+    futures::{AsyncReadExt, AsyncWriteExt, StreamExt},
+    PeerId, Stream, StreamProtocol,
+};
+use libp2p_stream::Control;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::ipc::Channel;
+use tokio::{
+    fs,
+    io::{AsyncReadExt as _, AsyncSeekExt as _, AsyncWriteExt as _},
+    sync::broadcast,
+    task::{self, AbortHandle, JoinHandle},
+    time::{self, Instant},
+};
+
+use super::{
+    streams::{read_frame, write_frame, Framing},
+    NodeEvent,
+};
+
+/// 文件传输协议
+pub const FILE_PROTOCOL: StreamProtocol = StreamProtocol::new("/swarmbook/file/1.0.0");
+
+/// 发送端每次从磁盘读取并写入流的字节数
+const CHUNK_SIZE: usize = 256 * 1024;
+/// 读取请求和响应头时的缓冲大小
+const FRAME_BUF: usize = 64 * 1024;
+/// 打开流和等待响应头的最长时间
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// 进度事件的最小间隔
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+/// `file_id` 的长度：SHA-256 与 BLAKE3 都是 32 字节，十六进制编码后 64 个字符
+const FILE_ID_LEN: usize = 64;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    #[default]
+    Sha256,
+    Blake3,
+}
+
+/// 对外提供的文件，`file_id` 即文件内容的哈希
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileOffer {
+    pub file_id: String,
+    pub name: String,
+    pub size: u64,
+    pub algorithm: HashAlgorithm,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+enum Request {
+    List,
+    /// 从 `offset` 开始发送，用于断点续传
+    #[serde(rename_all = "camelCase")]
+    Get {
+        file_id: String,
+        offset: u64,
+    },
+}
+
+/// `Header` 之后紧跟文件从 `offset` 到末尾的原始字节，发送完毕即关闭写端
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+enum Response {
+    Files { files: Vec<FileOffer> },
+    Header { offer: FileOffer, offset: u64 },
+    Error { message: String },
+}
+
+/// 下载进度，通过 [`Channel`] 推送给发起下载的页面
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum TransferEvent {
+    /// `offset` 大于 0 表示从上次中断处续传
+    #[serde(rename_all = "camelCase")]
+    Started {
+        transfer_id: u64,
+        offer: FileOffer,
+        offset: u64,
+    },
+    #[serde(rename_all = "camelCase")]
+    Progress {
+        transfer_id: u64,
+        received: u64,
+        total: u64,
+        bytes_per_sec: u64,
+    },
+    /// 接收完毕，正在计算哈希
+    #[serde(rename_all = "camelCase")]
+    Verifying { transfer_id: u64 },
+    #[serde(rename_all = "camelCase")]
+    Completed {
+        transfer_id: u64,
+        path: String,
+        elapsed_ms: u128,
+    },
+    /// 已接收的部分保留在 `.part` 文件中，再次下载时续传
+    #[serde(rename_all = "camelCase")]
+    Failed { transfer_id: u64, error: String },
+}
+
+/// 发送端事件，通过节点事件总线推送
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum FileEvent {
+    #[serde(rename_all = "camelCase")]
+    Serving {
+        peer_id: String,
+        file_id: String,
+        offset: u64,
+    },
+    #[serde(rename_all = "camelCase")]
+    Served {
+        peer_id: String,
+        file_id: String,
+        bytes: u64,
+    },
+    #[serde(rename_all = "camelCase")]
+    ServeFailed {
+        peer_id: String,
+        file_id: Option<String>,
+        error: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadedFile {
+    pub path: String,
+    pub offer: FileOffer,
+    /// 续传时本次开始的位置
+    pub resumed_from: u64,
+}
+
+struct Offered {
+    offer: FileOffer,
+    path: PathBuf,
+}
+
+#[derive(Default)]
+struct State {
+    offers: HashMap<String, Offered>,
+    next_transfer: u64,
+    transfers: HashMap<u64, AbortHandle>,
+    /// 正在写入的 `.part` 文件，同一文件同时只能有一个下载
+    parts: HashSet<PathBuf>,
+}
+
+/// 在 [`FILE_PROTOCOL`] 上提供文件并下载其他节点的文件。
+/// 与原始流一样不经过事件循环，节点启动时即开始接受请求
+#[derive(Clone)]
+pub struct Files {
+    control: Control,
+    state: Arc<StdMutex<State>>,
+    server: Arc<JoinHandle<()>>,
+}
+
+impl Files {
+    pub fn new(mut control: Control, events: broadcast::Sender<NodeEvent>) -> Self {
+        let state = Arc::<StdMutex<State>>::default();
+        // 每个节点只创建一次，协议不可能已被注册
+        let mut incoming = control
+            .accept(FILE_PROTOCOL)
+            .expect("file protocol is registered once per node");
+        let server = {
+            let state = state.clone();
+            tokio::spawn(async move {
+                while let Some((peer, stream)) = incoming.next().await {
+                    tokio::spawn(serve(state.clone(), events.clone(), peer, stream));
+                }
+            })
+        };
+        Self {
+            control,
+            state,
+            server: Arc::new(server),
+        }
+    }
+
+    /// 计算哈希后对外提供文件，内容相同的文件只保留最后提供的路径
+    pub async fn offer(&self, path: &str, algorithm: HashAlgorithm) -> Result<FileOffer> {
+        let path = PathBuf::from(path.trim());
+        let metadata = fs::metadata(&path)
+            .await
+            .map_err(|e| anyhow!("Cannot read {}: {}", path.display(), e))?;
+        if !metadata.is_file() {
+            bail!("{} is not a file", path.display());
+        }
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .ok_or_else(|| anyhow!("{} has no file name", path.display()))?;
+        let hash = hash_file(path.clone(), algorithm).await?;
+        let offer = FileOffer {
+            file_id: hash,
+            name,
+            size: metadata.len(),
+            algorithm,
+        };
+        self.lock().offers.insert(
+            offer.file_id.clone(),
+            Offered {
+                offer: offer.clone(),
+                path,
+            },
+        );
+        Ok(offer)
+    }
+
+    pub fn remove_offer(&self, file_id: &str) -> Result<()> {
+        self.lock()
+            .offers
+            .remove(file_id)
+            .map(|_| ())
+            .ok_or_else(|| anyhow!("File {} is not offered", file_id))
+    }
+
+    pub fn offers(&self) -> Vec<FileOffer> {
+        list_offers(&self.state)
+    }
+
+    /// 查询对方提供的文件
+    pub async fn remote_offers(&self, peer: PeerId) -> Result<Vec<FileOffer>> {
+        let mut stream = self.open(peer).await?;
+        match request(&mut stream, &Request::List).await? {
+            Response::Files { files } => Ok(files),
+            Response::Error { message } => Err(anyhow!(message)),
+            Response::Header { .. } => Err(anyhow!("Unexpected response from {}", peer)),
+        }
+    }
+
+    /// 下载文件到 `dest_dir`。先写入 `.part` 文件，存在时从其末尾续传，
+    /// 校验哈希通过后才改为最终文件名，同名文件已存在时另取名字
+    pub async fn download(
+        &self,
+        peer: PeerId,
+        file_id: &str,
+        dest_dir: &str,
+        progress: Channel<TransferEvent>,
+    ) -> Result<DownloadedFile> {
+        validate_file_id(file_id)?;
+        let dest_dir = PathBuf::from(dest_dir.trim());
+        if !fs::metadata(&dest_dir).await.is_ok_and(|m| m.is_dir()) {
+            bail!("{} is not a directory", dest_dir.display());
+        }
+        // 规范化后再比较，同一目录的不同写法也会用到同一个 `.part`
+        let dest_dir = fs::canonicalize(&dest_dir).await?;
+        // `file_id` 已校验为十六进制，可以直接用作文件名
+        let part_path = dest_dir.join(format!("{}.part", &file_id[..16]));
+        let transfer_id = {
+            let mut state = self.lock();
+            if !state.parts.insert(part_path.clone()) {
+                bail!("{} is already being downloaded", part_path.display());
+            }
+            state.next_transfer += 1;
+            state.next_transfer
+        };
+
+        let task = tokio::spawn({
+            let files = self.clone();
+            let file_id = file_id.to_string();
+            let part_path = part_path.clone();
+            let progress = progress.clone();
+            async move {
+                files
+                    .receive(transfer_id, peer, file_id, dest_dir, part_path, progress)
+                    .await
+            }
+        });
+        self.lock()
+            .transfers
+            .insert(transfer_id, task.abort_handle());
+        let result = match task.await {
+            Ok(result) => result,
+            Err(e) if e.is_cancelled() => Err(anyhow!("Transfer {} was cancelled", transfer_id)),
+            Err(e) => Err(anyhow!("Transfer {} panicked: {}", transfer_id, e)),
+        };
+        {
+            let mut state = self.lock();
+            state.transfers.remove(&transfer_id);
+            state.parts.remove(&part_path);
+        }
+
+        if let Err(e) = &result {
+            let _ = progress.send(TransferEvent::Failed {
+                transfer_id,
+                error: e.to_string(),
+            });
+        }
+        result
+    }
+
+    /// 中止下载，已接收的部分留待续传
+    pub fn cancel(&self, transfer_id: u64) -> Result<()> {
+        self.lock()
+            .transfers
+            .get(&transfer_id)
+            .map(AbortHandle::abort)
+            .ok_or_else(|| anyhow!("Transfer {} is not running", transfer_id))
+    }
+
+    /// 节点停止时结束服务和所有下载
+    pub fn abort_all(&self) {
+        self.server.abort();
+        for (_, transfer) in self.lock().transfers.drain() {
+            transfer.abort();
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        lock(&self.state)
+    }
+
+    async fn open(&self, peer: PeerId) -> Result<Stream> {
+        time::timeout(
+            REQUEST_TIMEOUT,
+            self.control.clone().open_stream(peer, FILE_PROTOCOL),
+        )
+        .await
+        .map_err(|_| anyhow!("Opening file stream to {} timed out", peer))?
+        .map_err(|e| anyhow!("Failed to open file stream to {}: {}", peer, e))
+    }
+
+    async fn receive(
+        &self,
+        transfer_id: u64,
+        peer: PeerId,
+        file_id: String,
+        dest_dir: PathBuf,
+        part_path: PathBuf,
+        progress: Channel<TransferEvent>,
+    ) -> Result<DownloadedFile> {
+        let started = Instant::now();
+        let mut offset = match fs::metadata(&part_path).await {
+            Ok(metadata) => metadata.len(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => 0,
+            Err(e) => return Err(e.into()),
+        };
+        if offset > 0 {
+            // `.part` 比文件还大说明是旧的残留，对方会拒绝这个位置，只能从头下载
+            let size = self
+                .remote_offers(peer)
+                .await?
+                .into_iter()
+                .find(|offer| offer.file_id == file_id)
+                .map(|offer| offer.size);
+            if size.is_some_and(|size| offset > size) {
+                offset = 0;
+            }
+        }
+
+        let mut stream = self.open(peer).await?;
+        let request_offset = offset;
+        let offer = match request(
+            &mut stream,
+            &Request::Get {
+                file_id: file_id.clone(),
+                offset: request_offset,
+            },
+        )
+        .await?
+        {
+            Response::Header { offer, offset } if offset == request_offset => offer,
+            Response::Error { message } => bail!(message),
+            _ => bail!("Unexpected response from {}", peer),
+        };
+        if offer.file_id != file_id {
+            bail!("{} answered with a different file", peer);
+        }
+        let mut part = fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(false)
+            .open(&part_path)
+            .await?;
+        // 丢掉多出的部分，从头下载时即清空旧内容
+        part.set_len(offset).await?;
+        part.seek(io::SeekFrom::Start(offset)).await?;
+        let _ = progress.send(TransferEvent::Started {
+            transfer_id,
+            offer: offer.clone(),
+            offset,
+        });
+
+        let mut buf = vec![0; CHUNK_SIZE];
+        let mut last_report = Instant::now();
+        let mut last_received = offset;
+        while offset < offer.size {
+            let n = stream.read(&mut buf).await?;
+            if n == 0 {
+                bail!("Transfer interrupted at {} of {} bytes", offset, offer.size);
+            }
+            let n = n.min((offer.size - offset) as usize);
+            part.write_all(&buf[..n]).await?;
+            offset += n as u64;
+
+            let elapsed = last_report.elapsed();
+            if elapsed >= PROGRESS_INTERVAL || offset == offer.size {
+                let _ = progress.send(TransferEvent::Progress {
+                    transfer_id,
+                    received: offset,
+                    total: offer.size,
+                    bytes_per_sec: ((offset - last_received) as f64 / elapsed.as_secs_f64()) as u64,
+                });
+                last_report = Instant::now();
+                last_received = offset;
+            }
+        }
+        part.flush().await?;
+        drop(part);
+        let _ = stream.close().await;
+
+        let _ = progress.send(TransferEvent::Verifying { transfer_id });
+        let hash = hash_file(part_path.clone(), offer.algorithm).await?;
+        if hash != offer.file_id {
+            // 内容有误时续传没有意义，丢弃重新下载
+            let _ = fs::remove_file(&part_path).await;
+            bail!("Hash mismatch: expected {}, got {}", offer.file_id, hash);
+        }
+        let path = unique_path(&dest_dir, &offer.name).await?;
+        fs::rename(&part_path, &path).await?;
+
+        let path = path.to_string_lossy().into_owned();
+        let _ = progress.send(TransferEvent::Completed {
+            transfer_id,
+            path: path.clone(),
+            elapsed_ms: started.elapsed().as_millis(),
+        });
+        Ok(DownloadedFile {
+            path,
+            offer,
+            resumed_from: request_offset,
+        })
+    }
+}
+
+/// `file_id` 来自前端，会拼进 `.part` 文件名，只接受小写十六进制的哈希
+fn validate_file_id(file_id: &str) -> Result<()> {
+    if file_id.len() != FILE_ID_LEN
+        || !file_id
+            .bytes()
+            .all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+    {
+        bail!(
+            "Invalid file id {:?}: expected {} lowercase hex characters",
+            file_id,
+            FILE_ID_LEN
+        );
+    }
+    Ok(())
+}
+
+fn lock(state: &StdMutex<State>) -> std::sync::MutexGuard<'_, State> {
+    state.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn list_offers(state: &StdMutex<State>) -> Vec<FileOffer> {
+    let mut offers: Vec<_> = lock(state)
+        .offers
+        .values()
+        .map(|o| o.offer.clone())
+        .collect();
+    offers.sort_by(|a, b| a.name.cmp(&b.name));
+    offers
+}
+
+/// 发送请求帧并读取响应帧
+async fn request(stream: &mut Stream, request: &Request) -> Result<Response> {
+    write_frame(
+        stream,
+        Framing::LengthPrefixed,
+        &serde_json::to_vec(request)?,
+    )
+    .await?;
+    let mut buf = vec![0; FRAME_BUF];
+    let frame = time::timeout(
+        REQUEST_TIMEOUT,
+        read_frame(stream, Framing::LengthPrefixed, &mut buf),
+    )
+    .await
+    .map_err(|_| anyhow!("Timed out waiting for a response"))??
+    .ok_or_else(|| anyhow!("Stream closed before a response"))?;
+    Ok(serde_json::from_slice(&frame)?)
+}
+
+/// 处理一个入站请求，出错时尽量把原因发给对方
+async fn serve(
+    state: Arc<StdMutex<State>>,
+    events: broadcast::Sender<NodeEvent>,
+    peer: PeerId,
+    mut stream: Stream,
+) {
+    let mut buf = vec![0; FRAME_BUF];
+    let request = match read_frame(&mut stream, Framing::LengthPrefixed, &mut buf).await {
+        Ok(Some(frame)) => serde_json::from_slice::<Request>(&frame).map_err(|e| e.to_string()),
+        Ok(None) => return,
+        Err(e) => Err(e.to_string()),
+    };
+    let result = match request {
+        Ok(Request::List) => {
+            let files = list_offers(&state);
+            send(&mut stream, &Response::Files { files }).await
+        }
+        Ok(Request::Get { file_id, offset }) => {
+            let offered = lock(&state)
+                .offers
+                .get(&file_id)
+                .map(|o| (o.offer.clone(), o.path.clone()));
+            let result = match offered {
+                Some((offer, path)) => {
+                    let _ = events.send(NodeEvent::File(FileEvent::Serving {
+                        peer_id: peer.to_string(),
+                        file_id: file_id.clone(),
+                        offset,
+                    }));
+                    send_file(&mut stream, offer, &path, offset).await
+                }
+                None => {
+                    let message = format!("File {} is not offered", file_id);
+                    let _ = send(
+                        &mut stream,
+                        &Response::Error {
+                            message: message.clone(),
+                        },
+                    )
+                    .await;
+                    Err(anyhow!(message))
+                }
+            };
+            let event = match &result {
+                Ok(bytes) => FileEvent::Served {
+                    peer_id: peer.to_string(),
+                    file_id,
+                    bytes: *bytes,
+                },
+                Err(e) => FileEvent::ServeFailed {
+                    peer_id: peer.to_string(),
+                    file_id: Some(file_id),
+                    error: e.to_string(),
+                },
+            };
+            let _ = events.send(NodeEvent::File(event));
+            Ok(())
+        }
+        Err(error) => {
+            let _ = events.send(NodeEvent::File(FileEvent::ServeFailed {
+                peer_id: peer.to_string(),
+                file_id: None,
+                error: error.clone(),
+            }));
+            send(&mut stream, &Response::Error { message: error }).await
+        }
+    };
+    if result.is_ok() {
+        let _ = stream.close().await;
+    }
+}
+
+async fn send(stream: &mut Stream, response: &Response) -> Result<()> {
+    write_frame(
+        stream,
+        Framing::LengthPrefixed,
+        &serde_json::to_vec(response)?,
+    )
+    .await?;
+    Ok(())
+}
+
+/// 发送响应头和文件内容，返回发送的字节数。
+/// 响应头之前的错误会告知对方，之后出错只能直接断开，由对方续传
+async fn send_file(stream: &mut Stream, offer: FileOffer, path: &Path, offset: u64) -> Result<u64> {
+    let mut file = match open_at(&offer, path, offset).await {
+        Ok(file) => file,
+        Err(e) => {
+            let message = e.to_string();
+            let _ = send(stream, &Response::Error { message }).await;
+            return Err(e);
+        }
+    };
+    send(stream, &Response::Header { offer, offset }).await?;
+
+    let mut buf = vec![0; CHUNK_SIZE];
+    let mut sent = 0;
+    loop {
+        let n = file.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        stream.write_all(&buf[..n]).await?;
+        sent += n as u64;
+    }
+    stream.flush().await?;
+    Ok(sent)
+}
+
+async fn open_at(offer: &FileOffer, path: &Path, offset: u64) -> Result<fs::File> {
+    let mut file = fs::File::open(path).await?;
+    if file.metadata().await?.len() != offer.size {
+        bail!("{} has changed since it was offered", offer.name);
+    }
+    if offset > offer.size {
+        bail!("Offset {} is beyond the end of {}", offset, offer.name);
+    }
+    file.seek(io::SeekFrom::Start(offset)).await?;
+    Ok(file)
+}
+
+/// 在阻塞线程上计算文件哈希，返回十六进制字符串
+async fn hash_file(path: PathBuf, algorithm: HashAlgorithm) -> Result<String> {
+    task::spawn_blocking(move || -> io::Result<String> {
+        let mut file = std::fs::File::open(path)?;
+        let mut buf = vec![0; CHUNK_SIZE];
+        match algorithm {
+            HashAlgorithm::Sha256 => {
+                let mut hasher = Sha256::new();
+                loop {
+                    let n = file.read(&mut buf)?;
+                    if n == 0 {
+                        break;
+                    }
+                    hasher.update(&buf[..n]);
+                }
+                Ok(hex::encode(hasher.finalize()))
+            }
+            HashAlgorithm::Blake3 => {
+                let mut hasher = blake3::Hasher::new();
+                loop {
+                    let n = file.read(&mut buf)?;
+                    if n == 0 {
+                        break;
+                    }
+                    hasher.update(&buf[..n]);
+                }
+                Ok(hasher.finalize().to_hex().to_string())
+            }
+        }
+    })
+    .await?
+    .map_err(Into::into)
+}
+
+/// 对方给的文件名只取最后一段，防止写到目标目录之外
+pub(super) async fn unique_path(dir: &Path, name: &str) -> Result<PathBuf> {
+    let name = Path::new(name)
+        .file_name()
+        .filter(|name| !name.is_empty())
+        .ok_or_else(|| anyhow!("Invalid file name {}", name))?;
+    let path = dir.join(name);
+    if fs::metadata(&path).await.is_err() {
+        return Ok(path);
+    }
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+    for n in 1.. {
+        let path = dir.join(format!("{stem} ({n}){extension}"));
+        if fs::metadata(&path).await.is_err() {
+            return Ok(path);
+        }
+    }
+    unreachable!()
+}
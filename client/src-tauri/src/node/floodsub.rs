@@ -0,0 +1,171 @@
+use std::{
+    ops::{Deref, DerefMut},
+    task::{Context, Poll},
+};
+
+use anyhow::Result;
+use libp2p::{
+    core::{transport::PortUse, Endpoint},
+    floodsub::{self, Topic},
+    swarm::{
+        ConnectionDenied, ConnectionId, FromSwarm, NetworkBehaviour, THandler, THandlerInEvent,
+        THandlerOutEvent, ToSwarm,
+    },
+    Multiaddr, PeerId,
+};
+use serde::Serialize;
+
+use super::{pubsub, NodeEvent};
+
+/// floodsub 只向 partial view 中的节点转发，并会主动拨号、在断开后重连这些节点。
+/// 这里由事件循环把已连接的节点加入 partial view，拨号仍由节点自己决定
+pub struct Floodsub(floodsub::Behaviour);
+
+impl Floodsub {
+    pub fn new(local_peer_id: PeerId) -> Self {
+        Self(floodsub::Behaviour::new(local_peer_id))
+    }
+}
+
+impl Deref for Floodsub {
+    type Target = floodsub::Behaviour;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Floodsub {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl NetworkBehaviour for Floodsub {
+    type ConnectionHandler = THandler<floodsub::Behaviour>;
+    type ToSwarm = floodsub::Event;
+
+    fn handle_pending_inbound_connection(
+        &mut self,
+        connection_id: ConnectionId,
+        local_addr: &Multiaddr,
+        remote_addr: &Multiaddr,
+    ) -> Result<(), ConnectionDenied> {
+        self.0
+            .handle_pending_inbound_connection(connection_id, local_addr, remote_addr)
+    }
+
+    fn handle_established_inbound_connection(
+        &mut self,
+        connection_id: ConnectionId,
+        peer: PeerId,
+        local_addr: &Multiaddr,
+        remote_addr: &Multiaddr,
+    ) -> Result<THandler<Self>, ConnectionDenied> {
+        self.0
+            .handle_established_inbound_connection(connection_id, peer, local_addr, remote_addr)
+    }
+
+    fn handle_pending_outbound_connection(
+        &mut self,
+        connection_id: ConnectionId,
+        maybe_peer: Option<PeerId>,
+        addresses: &[Multiaddr],
+        effective_role: Endpoint,
+    ) -> Result<Vec<Multiaddr>, ConnectionDenied> {
+        self.0.handle_pending_outbound_connection(
+            connection_id,
+            maybe_peer,
+            addresses,
+            effective_role,
+        )
+    }
+
+    fn handle_established_outbound_connection(
+        &mut self,
+        connection_id: ConnectionId,
+        peer: PeerId,
+        addr: &Multiaddr,
+        role_override: Endpoint,
+        port_use: PortUse,
+    ) -> Result<THandler<Self>, ConnectionDenied> {
+        self.0.handle_established_outbound_connection(
+            connection_id,
+            peer,
+            addr,
+            role_override,
+            port_use,
+        )
+    }
+
+    fn on_swarm_event(&mut self, event: FromSwarm) {
+        self.0.on_swarm_event(event)
+    }
+
+    fn on_connection_handler_event(
+        &mut self,
+        peer_id: PeerId,
+        connection_id: ConnectionId,
+        event: THandlerOutEvent<Self>,
+    ) {
+        self.0
+            .on_connection_handler_event(peer_id, connection_id, event)
+    }
+
+    fn poll(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<ToSwarm<Self::ToSwarm, THandlerInEvent<Self>>> {
+        loop {
+            match self.0.poll(cx) {
+                // 加入 partial view 和断线后的重连拨号
+                Poll::Ready(ToSwarm::Dial { .. }) => continue,
+                poll => return poll,
+            }
+        }
+    }
+}
+
+/// floodsub 没有话题哈希，话题名与 gossipsub 使用同样的规则
+pub fn topic(name: &str) -> Result<Topic> {
+    Ok(Topic::new(pubsub::topic(name)?.hash().into_string()))
+}
+
+/// floodsub 事件
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum FloodsubEvent {
+    /// 收到消息。floodsub 不区分转发者，`source` 是原始发布者
+    #[serde(rename_all = "camelCase")]
+    Message {
+        topics: Vec<String>,
+        source: String,
+        /// 随机生成的 20 字节序号
+        sequence_number: String,
+        data: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    Subscribed { peer_id: String, topic: String },
+    #[serde(rename_all = "camelCase")]
+    Unsubscribed { peer_id: String, topic: String },
+}
+
+pub fn map_event(event: floodsub::Event) -> NodeEvent {
+    let event = match event {
+        floodsub::Event::Message(message) => FloodsubEvent::Message {
+            topics: message.topics.iter().map(|t| t.id().to_string()).collect(),
+            source: message.source.to_string(),
+            sequence_number: hex::encode(&message.sequence_number),
+            data: String::from_utf8_lossy(&message.data).into_owned(),
+        },
+        floodsub::Event::Subscribed { peer_id, topic } => FloodsubEvent::Subscribed {
+            peer_id: peer_id.to_string(),
+            topic: topic.id().to_string(),
+        },
+        floodsub::Event::Unsubscribed { peer_id, topic } => FloodsubEvent::Unsubscribed {
+            peer_id: peer_id.to_string(),
+            topic: topic.id().to_string(),
+        },
+    };
+    NodeEvent::Floodsub(event)
+}
@@ -0,0 +1,625 @@
+mod behaviour;
+pub use behaviour::*;
+mod bootstrap;
+mod command;
+pub use command::*;
+mod config;
+pub use config::*;
+mod dht;
+pub use dht::{KBucketEntry, KadPeer, KadQueryResult, KAD_PROTOCOL};
+mod pubsub;
+pub use pubsub::{GossipsubEvent, TopicMesh};
+mod floodsub;
+pub use floodsub::FloodsubEvent;
+mod compare;
+pub use compare::{ComparisonReport, LatencyStats, ProtocolReport, PubsubProtocol};
+mod score;
+mod traffic;
+pub use score::{PeerScore, ScoreConfig, TopicScore, TopicScoreConfig};
+mod validation;
+pub use validation::{MessageValidator, Rejection, ValidatorConfig, Verdict};
+mod rendezvous;
+pub use rendezvous::{Registration, RendezvousEvent};
+mod rpc;
+pub use rpc::{Responder, RpcConfig, RpcEvent, RpcFormat, RpcReply, RpcResponse};
+mod streams;
+pub use streams::{
+    AcceptHandler, Encoding, Framing, StreamDirection, StreamEvent, StreamHandler, StreamInfo,
+    StreamOptions, Streams, StreamsSnapshot,
+};
+mod files;
+pub use files::{
+    DownloadedFile, FileEvent, FileOffer, Files, HashAlgorithm, TransferEvent, FILE_PROTOCOL,
+};
+mod relay;
+pub use relay::{CircuitLimit, RelayEvent, RelayServerConfig};
+mod blocks;
+pub use blocks::{AddedDag, BlockEvent, BlockInfo, Blocks, FetchedDag, BLOCK_PROTOCOL};
+mod event;
+pub use event::*;
+mod event_loop;
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    time::Duration,
+};
+
+use anyhow::{anyhow, bail, Result};
+use cid::Cid;
+use libp2p::{
+    futures::future, identity::Keypair, noise, quic, tcp, yamux, PeerId, Swarm, SwarmBuilder,
+    Transport,
+};
+use serde::Serialize;
+use serde_json::Value;
+use tauri::ipc::Channel;
+use tokio::{
+    sync::{broadcast, mpsc, oneshot, watch, Mutex},
+    task::JoinHandle,
+    time,
+};
+
+use crate::commands::{validate_bootstrap_addr, validate_listen_addr};
+use event_loop::EventLoop;
+use traffic::{MeteredMuxer, MeteredUpgrade, Traffic};
+
+/// 使用密钥库身份的主节点，各页面默认挂载到它
+pub const DEFAULT_NODE: &str = "default";
+
+/// 停止节点时等待事件循环和页面转发任务结束的最长时间
+const STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 正在运行的节点句柄
+struct NodeHandle {
+    peer_id: PeerId,
+    requests: mpsc::Sender<NodeRequest>,
+    events: broadcast::Sender<NodeEvent>,
+    state: watch::Receiver<NodeState>,
+    streams: Streams,
+    files: Files,
+    blocks: Blocks,
+    /// 事件循环任务
+    task: JoinHandle<()>,
+    /// 页面名 -> 事件转发任务
+    attachments: HashMap<String, JoinHandle<()>>,
+}
+
+impl NodeHandle {
+    /// 释放请求发送端让事件循环关闭监听器和连接，
+    /// 等它退出后页面会收到剩余事件，随后转发任务自行结束
+    async fn shutdown(self) {
+        let NodeHandle {
+            requests,
+            events,
+            streams,
+            files,
+            blocks,
+            task,
+            attachments,
+            ..
+        } = self;
+        streams.abort_all();
+        files.abort_all();
+        blocks.abort_all();
+        drop(requests);
+
+        let abort = task.abort_handle();
+        if time::timeout(STOP_TIMEOUT, task).await.is_err() {
+            tracing::warn!("node event loop did not stop in time, aborting");
+            abort.abort();
+            let _ = events.send(NodeEvent::StateChanged {
+                state: NodeState::Stopped,
+            });
+        }
+        drop(events);
+
+        for (_, task) in attachments {
+            let abort = task.abort_handle();
+            if time::timeout(STOP_TIMEOUT, task).await.is_err() {
+                abort.abort();
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeInfo {
+    pub node_id: String,
+    pub peer_id: String,
+    pub state: NodeState,
+}
+
+/// 节点注册表。除主节点外还可以在进程内启动多个实验节点，
+/// 每个节点有独立的事件总线，页面通过 [`NodeManager::attach`] 挂载
+pub struct NodeManager {
+    nodes: Mutex<HashMap<String, NodeHandle>>,
+    /// 正在启动的节点。建立 swarm 期间不持有 `nodes` 锁，以免其他节点的命令被阻塞
+    starting: Mutex<HashSet<String>>,
+    /// 应用数据目录，各节点的块存储位于 `blocks/<PeerId>` 下
+    data_dir: PathBuf,
+}
+
+/// TCP、QUIC 与 WebSocket 三种传输，外层包 DNS 解析以支持 `/dns4`、`/dnsaddr` 地址。
+/// 各传输的多路复用层都包了一层流量统计
+async fn build_swarm(
+    keypair: Keypair,
+    config: &NodeConfig,
+) -> Result<(Swarm<NodeBehaviour>, Traffic)> {
+    let traffic = Traffic::default();
+    let (tcp_traffic, quic_traffic, ws_traffic, relay_traffic) = (
+        traffic.clone(),
+        traffic.clone(),
+        traffic.clone(),
+        traffic.clone(),
+    );
+    let swarm = SwarmBuilder::with_existing_identity(keypair)
+        .with_tokio()
+        .with_tcp(tcp::Config::default(), noise::Config::new, move || {
+            MeteredUpgrade::new(yamux::Config::default(), tcp_traffic)
+        })?
+        .with_other_transport(|keypair| {
+            quic::tokio::Transport::new(quic::Config::new(keypair))
+                .map(move |(peer_id, muxer), _| (peer_id, MeteredMuxer::new(muxer, quic_traffic)))
+        })?
+        .with_dns()?
+        .with_websocket(noise::Config::new, move || {
+            MeteredUpgrade::new(yamux::Config::default(), ws_traffic)
+        })
+        .await?
+        .with_relay_client(noise::Config::new, move || {
+            MeteredUpgrade::new(yamux::Config::default(), relay_traffic)
+        })?
+        .with_behaviour(|keypair, relay_client| NodeBehaviour::new(keypair, relay_client, config))?
+        .with_swarm_config(|cfg| cfg.with_idle_connection_timeout(Duration::from_secs(u64::MAX)))
+        .build();
+    Ok((swarm, traffic))
+}
+
+impl NodeManager {
+    pub fn new(data_dir: PathBuf) -> Self {
+        Self {
+            nodes: Mutex::default(),
+            starting: Mutex::default(),
+            data_dir,
+        }
+    }
+
+    /// 以 `node_id` 启动节点，已在运行时直接返回其 PeerId（忽略 `keypair` 和 `config`），
+    /// 同一 `node_id` 正在启动时报错
+    pub async fn start(
+        &self,
+        node_id: &str,
+        keypair: Keypair,
+        config: NodeConfig,
+    ) -> Result<PeerId> {
+        {
+            let nodes = self.nodes.lock().await;
+            if let Some(node) = nodes.get(node_id) {
+                return Ok(node.peer_id);
+            }
+            if !self.starting.lock().await.insert(node_id.to_string()) {
+                bail!("Node {} is already starting", node_id);
+            }
+        }
+        let spawned = self.spawn(keypair, &config).await;
+        let peer_id = {
+            let mut nodes = self.nodes.lock().await;
+            self.starting.lock().await.remove(node_id);
+            let node = spawned?;
+            let peer_id = node.peer_id;
+            nodes.insert(node_id.to_string(), node);
+            peer_id
+        };
+
+        if config.auto_dial {
+            let others: Vec<_> = self
+                .nodes()
+                .await
+                .into_iter()
+                .filter(|other| other.node_id != node_id)
+                .collect();
+            for other in others {
+                // 单个节点拨号失败不影响启动，结果会通过事件推送
+                if let Err(e) = self.dial_node(node_id, &other.node_id).await {
+                    tracing::warn!("auto-dial {} -> {} failed: {e}", node_id, other.node_id);
+                }
+            }
+        }
+        Ok(peer_id)
+    }
+
+    /// 建立 swarm 和块存储并启动事件循环
+    async fn spawn(&self, keypair: Keypair, config: &NodeConfig) -> Result<NodeHandle> {
+        let listen_addrs = config
+            .listen_addrs
+            .iter()
+            .map(|addr| validate_listen_addr(addr))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| anyhow!(e))?;
+        let bootstrap_peers = config
+            .bootstrap_peers
+            .iter()
+            .flatten()
+            .map(|addr| validate_bootstrap_addr(addr))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| anyhow!(e))?;
+
+        let (swarm, traffic) = build_swarm(keypair, config).await?;
+        let peer_id = *swarm.local_peer_id();
+
+        let (request_tx, request_rx) = mpsc::channel(32);
+        let (event_tx, _) = broadcast::channel(256);
+        let (state_tx, state_rx) = watch::channel(NodeState::Starting);
+        let streams = Streams::new(swarm.behaviour().stream.new_control(), event_tx.clone());
+        let files = Files::new(swarm.behaviour().stream.new_control(), event_tx.clone());
+        let blocks = Blocks::new(
+            swarm.behaviour().stream.new_control(),
+            event_tx.clone(),
+            &self.data_dir.join("blocks").join(peer_id.to_base58()),
+        )
+        .await?;
+        let mut event_loop = EventLoop::new(swarm, request_rx, event_tx.clone(), state_tx);
+        event_loop.set_mdns_auto_dial(config.mdns_auto_dial);
+        event_loop.set_score_config(config.gossipsub_score.clone());
+        event_loop.set_traffic(traffic);
+        event_loop.set_responder(config.rpc.responder.clone());
+        event_loop.set_bootstrap_peers(bootstrap_peers);
+        for addr in listen_addrs {
+            event_loop.listen_on(addr)?;
+        }
+        let task = tokio::spawn(event_loop.run());
+
+        Ok(NodeHandle {
+            peer_id,
+            requests: request_tx,
+            events: event_tx,
+            state: state_rx,
+            streams,
+            files,
+            blocks,
+            task,
+            attachments: HashMap::new(),
+        })
+    }
+
+    /// 优雅停止节点，返回时事件循环和页面转发任务均已结束
+    pub async fn stop(&self, node_id: &str) {
+        let node = self.nodes.lock().await.remove(node_id);
+        if let Some(node) = node {
+            node.shutdown().await;
+        }
+    }
+
+    /// 停止所有节点，用于应用退出
+    pub async fn stop_all(&self) {
+        let nodes: Vec<_> = self
+            .nodes
+            .lock()
+            .await
+            .drain()
+            .map(|(_, node)| node)
+            .collect();
+        future::join_all(nodes.into_iter().map(NodeHandle::shutdown)).await;
+    }
+
+    pub async fn nodes(&self) -> Vec<NodeInfo> {
+        let mut nodes: Vec<_> = self
+            .nodes
+            .lock()
+            .await
+            .iter()
+            .map(|(node_id, node)| NodeInfo {
+                node_id: node_id.clone(),
+                peer_id: node.peer_id.to_string(),
+                state: *node.state.borrow(),
+            })
+            .collect();
+        nodes.sort_by(|a, b| a.node_id.cmp(&b.node_id));
+        nodes
+    }
+
+    pub async fn peer_id(&self, node_id: &str) -> Option<PeerId> {
+        self.nodes
+            .lock()
+            .await
+            .get(node_id)
+            .map(|node| node.peer_id)
+    }
+
+    /// 发送命令并等待事件循环的执行结果
+    pub async fn send(&self, node_id: &str, command: NodeCommand) -> Result<CommandReply> {
+        let (tx, rx) = oneshot::channel();
+        self.request(node_id, NodeRequest::Command(command, tx))
+            .await?;
+        rx.await.map_err(|_| not_running(node_id))?
+    }
+
+    /// 让 `node_id` 拨号同进程内的另一个节点
+    pub async fn dial_node(&self, node_id: &str, target: &str) -> Result<CommandReply> {
+        let peer_id = self
+            .peer_id(target)
+            .await
+            .ok_or_else(|| not_running(target))?;
+        let snapshot = self.snapshot(target).await?;
+        self.send(
+            node_id,
+            NodeCommand::DialPeer {
+                peer_id,
+                addrs: snapshot.listen_addrs,
+            },
+        )
+        .await
+    }
+
+    /// 各个已订阅话题的 mesh 节点
+    pub async fn mesh_peers(&self, node_id: &str) -> Result<Vec<TopicMesh>> {
+        let (tx, rx) = oneshot::channel();
+        self.request(node_id, NodeRequest::GossipsubMesh(tx))
+            .await?;
+        rx.await.map_err(|_| not_running(node_id))
+    }
+
+    /// 各话题 gossipsub 与 floodsub 的对比结果
+    pub async fn pubsub_comparison(&self, node_id: &str) -> Result<Vec<ComparisonReport>> {
+        let (tx, rx) = oneshot::channel();
+        self.request(node_id, NodeRequest::PubsubComparison(tx))
+            .await?;
+        rx.await.map_err(|_| not_running(node_id))
+    }
+
+    /// 各 gossipsub 节点的评分明细，按总分从高到低排列
+    pub async fn peer_scores(&self, node_id: &str) -> Result<Vec<PeerScore>> {
+        let (tx, rx) = oneshot::channel();
+        self.request(node_id, NodeRequest::GossipsubScores(tx))
+            .await?;
+        rx.await.map_err(|_| not_running(node_id))
+    }
+
+    /// 在话题的校验流水线末尾追加一个 Rust 侧实现的校验器，返回该话题的校验器数量
+    pub async fn add_validator(
+        &self,
+        node_id: &str,
+        topic: &str,
+        validator: Box<dyn MessageValidator>,
+    ) -> Result<usize> {
+        let (tx, rx) = oneshot::channel();
+        self.request(
+            node_id,
+            NodeRequest::AddValidator(topic.to_string(), validator, tx),
+        )
+        .await?;
+        rx.await.map_err(|_| not_running(node_id))?
+    }
+
+    /// 用节点的请求-响应协议发送请求并等待响应。`timeout` 只能比启动时配置的超时更短，
+    /// 调用方超时放弃后到达的响应仍会通过 `rpc` 事件推送
+    pub async fn rpc_request(
+        &self,
+        node_id: &str,
+        peer_id: PeerId,
+        request: Value,
+        timeout: Option<Duration>,
+    ) -> Result<RpcReply> {
+        if timeout.is_some_and(|timeout| timeout.is_zero()) {
+            bail!("Request timeout must be greater than 0");
+        }
+        let (tx, rx) = oneshot::channel();
+        self.request(node_id, NodeRequest::RpcRequest(peer_id, request, tx))
+            .await?;
+        let reply = match timeout {
+            Some(timeout) => time::timeout(timeout, rx).await.map_err(|_| {
+                anyhow!(
+                    "Request to {} timed out after {} ms",
+                    peer_id,
+                    timeout.as_millis()
+                )
+            })?,
+            None => rx.await,
+        };
+        reply.map_err(|_| not_running(node_id))?
+    }
+
+    /// 节点的原始流，打开和读写都不经过事件循环
+    pub async fn streams(&self, node_id: &str) -> Result<Streams> {
+        self.nodes
+            .lock()
+            .await
+            .get(node_id)
+            .map(|node| node.streams.clone())
+            .ok_or_else(|| not_running(node_id))
+    }
+
+    /// 节点的文件传输服务
+    pub async fn files(&self, node_id: &str) -> Result<Files> {
+        self.nodes
+            .lock()
+            .await
+            .get(node_id)
+            .map(|node| node.files.clone())
+            .ok_or_else(|| not_running(node_id))
+    }
+
+    /// 节点的块存储
+    pub async fn blocks(&self, node_id: &str) -> Result<Blocks> {
+        self.nodes
+            .lock()
+            .await
+            .get(node_id)
+            .map(|node| node.blocks.clone())
+            .ok_or_else(|| not_running(node_id))
+    }
+
+    /// 把文件加入块存储，并以根 CID 为键在 DHT 上发布 provider 记录
+    pub async fn add_dag_file(&self, node_id: &str, path: &str) -> Result<AddedDag> {
+        let added = self.blocks(node_id).await?.add_file(path).await?;
+        self.send(
+            node_id,
+            NodeCommand::KadStartProviding {
+                key: added.cid.clone(),
+            },
+        )
+        .await?;
+        Ok(added)
+    }
+
+    /// 按 CID 取回 DAG：先向已连接的节点发送 want-have，都没有时再通过 DHT 查找 provider。
+    /// 取回后本节点也发布 provider 记录
+    pub async fn fetch_dag(
+        &self,
+        node_id: &str,
+        cid: &str,
+        dest_dir: Option<&str>,
+    ) -> Result<FetchedDag> {
+        let cid = Cid::try_from(cid.trim()).map_err(|e| anyhow!("Invalid CID: {}", e))?;
+        let blocks = self.blocks(node_id).await?;
+        let mut peers = Vec::new();
+        if !blocks.has(&cid).await {
+            let mut connected: Vec<PeerId> = self
+                .snapshot(node_id)
+                .await?
+                .connections
+                .iter()
+                .filter_map(|c| c.peer_id.parse().ok())
+                .collect();
+            connected.sort();
+            connected.dedup();
+            peers = blocks.who_has(cid, connected).await;
+            if peers.is_empty() {
+                let providers = self.find_providers(node_id, &cid.to_string()).await?;
+                peers = blocks.who_has(cid, providers).await;
+            }
+        }
+        let fetched = blocks.fetch(cid, peers, dest_dir).await?;
+        self.send(
+            node_id,
+            NodeCommand::KadStartProviding {
+                key: fetched.cid.clone(),
+            },
+        )
+        .await?;
+        Ok(fetched)
+    }
+
+    /// 在 DHT 上查找 `key` 的 provider，返回第一批结果并开始拨号
+    pub async fn find_providers(&self, node_id: &str, key: &str) -> Result<Vec<PeerId>> {
+        let (tx, rx) = oneshot::channel();
+        self.request(node_id, NodeRequest::FindProviders(key.to_string(), tx))
+            .await?;
+        rx.await.map_err(|_| not_running(node_id))?
+    }
+
+    /// 当前 k-bucket 内容
+    pub async fn routing_table(&self, node_id: &str) -> Result<Vec<KBucketEntry>> {
+        let (tx, rx) = oneshot::channel();
+        self.request(node_id, NodeRequest::RoutingTable(tx)).await?;
+        rx.await.map_err(|_| not_running(node_id))
+    }
+
+    async fn snapshot(&self, node_id: &str) -> Result<NodeSnapshot> {
+        let (tx, rx) = oneshot::channel();
+        self.request(node_id, NodeRequest::Snapshot(tx)).await?;
+        rx.await.map_err(|_| not_running(node_id))
+    }
+
+    async fn request(&self, node_id: &str, request: NodeRequest) -> Result<()> {
+        let requests = self
+            .nodes
+            .lock()
+            .await
+            .get(node_id)
+            .map(|node| node.requests.clone())
+            .ok_or_else(|| not_running(node_id))?;
+        requests
+            .send(request)
+            .await
+            .map_err(|_| not_running(node_id))
+    }
+
+    /// 将页面挂载到节点事件总线，`map` 决定页面关心哪些事件。
+    /// 同一节点上同名页面重复挂载时会替换之前的转发任务。
+    pub async fn attach<T, F>(
+        &self,
+        node_id: &str,
+        name: &str,
+        channel: Channel<T>,
+        map: F,
+    ) -> Result<()>
+    where
+        T: Serialize + Clone + Send + 'static,
+        F: Fn(NodeEvent) -> Option<T> + Send + 'static,
+    {
+        let (mut events, state) = self
+            .nodes
+            .lock()
+            .await
+            .get(node_id)
+            .map(|node| (node.events.subscribe(), *node.state.borrow()))
+            .ok_or_else(|| not_running(node_id))?;
+
+        // 补发挂载之前已经发生的状态、监听和连接事件
+        let snapshot = self.snapshot(node_id).await?;
+        let replay = std::iter::once(NodeEvent::StateChanged { state })
+            .chain(
+                snapshot
+                    .listen_addrs
+                    .iter()
+                    .map(|addr| NodeEvent::Listening {
+                        addr: addr.to_string(),
+                    }),
+            )
+            .chain(snapshot.connections.into_iter().map(NodeEvent::Connected))
+            .chain(
+                snapshot
+                    .discovered
+                    .into_iter()
+                    .map(NodeEvent::MdnsDiscovered),
+            );
+        for event in replay.filter_map(&map) {
+            let _ = channel.send(event);
+        }
+
+        let task = tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(event) => {
+                        if let Some(event) = map(event) {
+                            if channel.send(event).is_err() {
+                                break;
+                            }
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::warn!("page event channel lagged, skipped {skipped} events");
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+
+        let mut nodes = self.nodes.lock().await;
+        let Some(node) = nodes.get_mut(node_id) else {
+            // 快照之后节点被停止
+            task.abort();
+            return Err(not_running(node_id));
+        };
+        if let Some(previous) = node.attachments.insert(name.to_string(), task) {
+            previous.abort();
+        }
+        Ok(())
+    }
+
+    /// 页面离开时停止转发，节点本身继续运行
+    pub async fn detach(&self, node_id: &str, name: &str) {
+        if let Some(node) = self.nodes.lock().await.get_mut(node_id) {
+            if let Some(task) = node.attachments.remove(name) {
+                task.abort();
+            }
+        }
+    }
+}
+
+fn not_running(node_id: &str) -> anyhow::Error {
+    anyhow!("Node {} is not running", node_id)
+}
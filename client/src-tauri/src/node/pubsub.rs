@@ -0,0 +1,201 @@
+use anyhow::{anyhow, Result};
+
+use libp2p::{
+    gossipsub::{self, IdentTopic, MessageAuthenticity, MessageId, PublishError, TopicScoreParams},
+    identity::Keypair,
+    PeerId,
+};
+use serde::Serialize;
+
+use super::{
+    score::{PeerScore, ScoreConfig},
+    validation::Rejection,
+    NodeEvent,
+};
+
+/// 单条 gossipsub RPC 的最大字节数，与库的默认值相同，超出的消息发布会失败
+pub const MAX_TRANSMIT_SIZE: usize = 65_536;
+/// 消息中除数据和话题外的部分：来源、序号、签名和非内嵌的公钥，以及 RPC 外层
+pub const MESSAGE_OVERHEAD: usize = 256;
+
+/// 消息用节点身份签名，接收方按来源和序号去重。
+/// 收到的消息先经过应用层校验再决定是否转发，校验拒绝会计入转发者的评分
+pub fn new_gossipsub(
+    keypair: &Keypair,
+    score: &ScoreConfig,
+) -> Result<gossipsub::Behaviour, Box<dyn std::error::Error + Send + Sync>> {
+    let config = gossipsub::ConfigBuilder::default()
+        .validation_mode(gossipsub::ValidationMode::Strict)
+        .validate_messages()
+        .max_transmit_size(MAX_TRANSMIT_SIZE)
+        .build()?;
+    let mut behaviour =
+        gossipsub::Behaviour::new(MessageAuthenticity::Signed(keypair.clone()), config)?;
+    // 话题参数在订阅时才用到，提前校验以便启动时就报错
+    score.topic_params().validate()?;
+    behaviour.with_peer_score(score.params(), score.thresholds())?;
+    Ok(behaviour)
+}
+
+/// 订阅话题并为其启用评分，返回 `false` 表示此前已经订阅
+pub fn subscribe(
+    gossipsub: &mut gossipsub::Behaviour,
+    topic: IdentTopic,
+    params: TopicScoreParams,
+) -> Result<bool> {
+    let subscribed = gossipsub.subscribe(&topic)?;
+    gossipsub
+        .set_topic_params(topic, params)
+        .map_err(|e| anyhow!(e))?;
+    Ok(subscribed)
+}
+
+/// 话题名直接作为 topic hash，前后端用同一个字符串即可
+pub fn topic(name: &str) -> Result<IdentTopic> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(anyhow!("Topic must not be empty"));
+    }
+    Ok(IdentTopic::new(name))
+}
+
+pub fn publish_error(topic: &str, error: PublishError) -> anyhow::Error {
+    match error {
+        PublishError::NoPeersSubscribedToTopic => {
+            anyhow!("No connected peers are subscribed to {}", topic)
+        }
+        PublishError::Duplicate => anyhow!("Message was already published"),
+        PublishError::MessageTooLarge => anyhow!("Message is too large"),
+        error => anyhow!("Failed to publish to {}: {}", topic, error),
+    }
+}
+
+/// 默认的消息 ID 是 PeerId 与序号拼成的文本，能按 UTF-8 解码时直接显示
+pub fn format_message_id(id: &MessageId) -> String {
+    String::from_utf8(id.0.clone()).unwrap_or_else(|_| id.to_string())
+}
+
+/// 已订阅话题的 mesh 状态
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TopicMesh {
+    pub topic: String,
+    /// 当前 mesh 中直接转发完整消息的节点
+    pub mesh_peers: Vec<String>,
+    /// 所有已知订阅了该话题的节点，包括只交换 gossip 的
+    pub subscribers: Vec<String>,
+}
+
+pub fn topic_mesh(gossipsub: &gossipsub::Behaviour) -> Vec<TopicMesh> {
+    let mut topics: Vec<_> = gossipsub
+        .topics()
+        .map(|topic| TopicMesh {
+            topic: topic.to_string(),
+            mesh_peers: gossipsub.mesh_peers(topic).map(|p| p.to_string()).collect(),
+            subscribers: gossipsub
+                .all_peers()
+                .filter(|(_, topics)| topics.contains(&topic))
+                .map(|(peer_id, _)| peer_id.to_string())
+                .collect(),
+        })
+        .collect();
+    topics.sort_by(|a, b| a.topic.cmp(&b.topic));
+    topics
+}
+
+/// gossipsub 事件
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum GossipsubEvent {
+    /// 收到消息。`source` 是原始发布者，`propagation_source` 是转发给我们的邻居
+    #[serde(rename_all = "camelCase")]
+    Message {
+        topic: String,
+        source: Option<String>,
+        propagation_source: String,
+        /// 以纳秒时间戳起始，超出 JS 安全整数范围，按字符串传递
+        sequence_number: Option<String>,
+        message_id: String,
+        /// 按 UTF-8 解码的消息内容
+        data: String,
+    },
+    /// 未通过校验的消息，`validator` 是拦下它的校验器
+    #[serde(rename_all = "camelCase")]
+    Invalid {
+        topic: String,
+        source: Option<String>,
+        propagation_source: String,
+        message_id: String,
+        acceptance: Rejection,
+        validator: String,
+        reason: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    Subscribed { peer_id: String, topic: String },
+    #[serde(rename_all = "camelCase")]
+    Unsubscribed { peer_id: String, topic: String },
+    /// 对方已连接但不支持 gossipsub
+    #[serde(rename_all = "camelCase")]
+    NotSupported { peer_id: String },
+    /// 对方来不及接收，上一个心跳周期内有消息发送失败
+    #[serde(rename_all = "camelCase")]
+    SlowPeer { peer_id: String, failed: usize },
+    /// 定时推送的各节点评分明细
+    Scores { peers: Vec<PeerScore> },
+}
+
+pub fn invalid_event(
+    propagation_source: &PeerId,
+    message_id: &MessageId,
+    message: &gossipsub::Message,
+    validator: &str,
+    acceptance: Rejection,
+    reason: String,
+) -> NodeEvent {
+    NodeEvent::Gossipsub(GossipsubEvent::Invalid {
+        topic: message.topic.to_string(),
+        source: message.source.map(|p| p.to_string()),
+        propagation_source: propagation_source.to_string(),
+        message_id: format_message_id(message_id),
+        acceptance,
+        validator: validator.to_string(),
+        reason,
+    })
+}
+
+pub fn map_event(event: gossipsub::Event) -> NodeEvent {
+    use gossipsub::Event;
+    let event = match event {
+        Event::Message {
+            propagation_source,
+            message_id,
+            message,
+        } => GossipsubEvent::Message {
+            topic: message.topic.to_string(),
+            source: message.source.map(|p| p.to_string()),
+            propagation_source: propagation_source.to_string(),
+            sequence_number: message.sequence_number.map(|n| n.to_string()),
+            message_id: format_message_id(&message_id),
+            data: String::from_utf8_lossy(&message.data).into_owned(),
+        },
+        Event::Subscribed { peer_id, topic } => GossipsubEvent::Subscribed {
+            peer_id: peer_id.to_string(),
+            topic: topic.to_string(),
+        },
+        Event::Unsubscribed { peer_id, topic } => GossipsubEvent::Unsubscribed {
+            peer_id: peer_id.to_string(),
+            topic: topic.to_string(),
+        },
+        Event::GossipsubNotSupported { peer_id } => GossipsubEvent::NotSupported {
+            peer_id: peer_id.to_string(),
+        },
+        Event::SlowPeer {
+            peer_id,
+            failed_messages,
+        } => GossipsubEvent::SlowPeer {
+            peer_id: peer_id.to_string(),
+            failed: failed_messages.total(),
+        },
+    };
+    NodeEvent::Gossipsub(event)
+}
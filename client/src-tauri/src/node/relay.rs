@@ -0,0 +1,228 @@
+use std::time::Duration;
+
+use libp2p::{multiaddr::Protocol, relay, Multiaddr, PeerId};
+use serde::{Deserialize, Serialize};
+
+use super::NodeEvent;
+
+/// 中继服务端的预约与中继连接上限，缺省值与 libp2p 相同
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RelayServerConfig {
+    /// 同时保有的预约总数
+    pub max_reservations: usize,
+    /// 单个节点经不同连接保有的预约数
+    pub max_reservations_per_peer: usize,
+    /// 预约有效期（秒），客户端会在到期前自动续约
+    pub reservation_duration_secs: u64,
+    /// 同时中继的连接总数
+    pub max_circuits: usize,
+    pub max_circuits_per_peer: usize,
+    /// 单条中继连接的时长上限（秒），超过后服务端关闭连接
+    pub max_circuit_duration_secs: u64,
+    /// 单条中继连接每个方向可转发的字节数
+    pub max_circuit_bytes: u64,
+}
+
+impl Default for RelayServerConfig {
+    fn default() -> Self {
+        let config = relay::Config::default();
+        Self {
+            max_reservations: config.max_reservations,
+            max_reservations_per_peer: config.max_reservations_per_peer,
+            reservation_duration_secs: config.reservation_duration.as_secs(),
+            max_circuits: config.max_circuits,
+            max_circuits_per_peer: config.max_circuits_per_peer,
+            max_circuit_duration_secs: config.max_circuit_duration.as_secs(),
+            max_circuit_bytes: config.max_circuit_bytes,
+        }
+    }
+}
+
+/// 速率限制沿用 libp2p 的默认值
+pub fn new_server(peer_id: PeerId, config: &RelayServerConfig) -> relay::Behaviour {
+    relay::Behaviour::new(
+        peer_id,
+        relay::Config {
+            max_reservations: config.max_reservations,
+            max_reservations_per_peer: config.max_reservations_per_peer,
+            reservation_duration: Duration::from_secs(config.reservation_duration_secs),
+            max_circuits: config.max_circuits,
+            max_circuits_per_peer: config.max_circuits_per_peer,
+            max_circuit_duration: Duration::from_secs(config.max_circuit_duration_secs),
+            max_circuit_bytes: config.max_circuit_bytes,
+            ..Default::default()
+        },
+    )
+}
+
+/// `/p2p/<中继>/p2p-circuit` 中的中继节点，不是中继地址时返回 `None`
+pub fn circuit_relay(addr: &Multiaddr) -> Option<PeerId> {
+    let mut relay = None;
+    for protocol in addr.iter() {
+        match protocol {
+            Protocol::P2p(peer_id) => relay = Some(peer_id),
+            Protocol::P2pCircuit => return relay,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// 中继服务端告知的连接限制
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CircuitLimit {
+    pub duration_secs: Option<u64>,
+    pub data_bytes: Option<u64>,
+}
+
+impl CircuitLimit {
+    /// libp2p 没有导出限制的类型，只能按字段构造
+    fn new(duration: Option<Duration>, data_bytes: Option<u64>) -> Self {
+        Self {
+            duration_secs: duration.map(|d| d.as_secs()),
+            data_bytes,
+        }
+    }
+}
+
+/// 中继服务端与客户端事件
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum RelayEvent {
+    /// 服务端接受了 `peer_id` 的预约，`renewed` 表示续约
+    #[serde(rename_all = "camelCase")]
+    ReservationAccepted { peer_id: String, renewed: bool },
+    /// 服务端拒绝预约，通常是超过了预约上限或速率限制
+    #[serde(rename_all = "camelCase")]
+    ReservationDenied { peer_id: String, status: String },
+    #[serde(rename_all = "camelCase")]
+    ReservationClosed { peer_id: String },
+    #[serde(rename_all = "camelCase")]
+    ReservationTimedOut { peer_id: String },
+    #[serde(rename_all = "camelCase")]
+    CircuitOpened {
+        src_peer_id: String,
+        dst_peer_id: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    CircuitDenied {
+        src_peer_id: String,
+        dst_peer_id: String,
+        status: String,
+    },
+    /// 中继连接关闭，超过时长或流量上限时带有错误
+    #[serde(rename_all = "camelCase")]
+    CircuitClosed {
+        src_peer_id: String,
+        dst_peer_id: String,
+        error: Option<String>,
+    },
+    /// 客户端在中继上的预约成功，之后可以通过中继地址被拨号
+    #[serde(rename_all = "camelCase")]
+    Reserved {
+        relay_peer_id: String,
+        renewal: bool,
+        limit: Option<CircuitLimit>,
+    },
+    /// 客户端预约失败，对应的中继监听器随之关闭
+    #[serde(rename_all = "camelCase")]
+    ReservationFailed {
+        relay_peer_id: String,
+        error: String,
+    },
+    /// 经中继拨出的连接已建立
+    #[serde(rename_all = "camelCase")]
+    OutboundCircuit {
+        relay_peer_id: String,
+        limit: Option<CircuitLimit>,
+    },
+    /// 其他节点经中继拨入
+    #[serde(rename_all = "camelCase")]
+    InboundCircuit {
+        src_peer_id: String,
+        limit: Option<CircuitLimit>,
+    },
+}
+
+pub fn map_server_event(event: relay::Event) -> Option<NodeEvent> {
+    let event = match event {
+        relay::Event::ReservationReqAccepted {
+            src_peer_id,
+            renewed,
+        } => RelayEvent::ReservationAccepted {
+            peer_id: src_peer_id.to_string(),
+            renewed,
+        },
+        relay::Event::ReservationReqDenied {
+            src_peer_id,
+            status,
+        } => RelayEvent::ReservationDenied {
+            peer_id: src_peer_id.to_string(),
+            status: format!("{:?}", status),
+        },
+        relay::Event::ReservationClosed { src_peer_id } => RelayEvent::ReservationClosed {
+            peer_id: src_peer_id.to_string(),
+        },
+        relay::Event::ReservationTimedOut { src_peer_id } => RelayEvent::ReservationTimedOut {
+            peer_id: src_peer_id.to_string(),
+        },
+        relay::Event::CircuitReqAccepted {
+            src_peer_id,
+            dst_peer_id,
+        } => RelayEvent::CircuitOpened {
+            src_peer_id: src_peer_id.to_string(),
+            dst_peer_id: dst_peer_id.to_string(),
+        },
+        relay::Event::CircuitReqDenied {
+            src_peer_id,
+            dst_peer_id,
+            status,
+        } => RelayEvent::CircuitDenied {
+            src_peer_id: src_peer_id.to_string(),
+            dst_peer_id: dst_peer_id.to_string(),
+            status: format!("{:?}", status),
+        },
+        relay::Event::CircuitClosed {
+            src_peer_id,
+            dst_peer_id,
+            error,
+        } => RelayEvent::CircuitClosed {
+            src_peer_id: src_peer_id.to_string(),
+            dst_peer_id: dst_peer_id.to_string(),
+            error: error.map(|e| e.to_string()),
+        },
+        // 其余失败事件已被标记为弃用，libp2p 会自行记录日志
+        _ => return None,
+    };
+    Some(NodeEvent::Relay(event))
+}
+
+pub fn map_client_event(event: relay::client::Event) -> NodeEvent {
+    let event = match event {
+        relay::client::Event::ReservationReqAccepted {
+            relay_peer_id,
+            renewal,
+            limit,
+        } => RelayEvent::Reserved {
+            relay_peer_id: relay_peer_id.to_string(),
+            renewal,
+            limit: limit.map(|l| CircuitLimit::new(l.duration(), l.data_in_bytes())),
+        },
+        relay::client::Event::OutboundCircuitEstablished {
+            relay_peer_id,
+            limit,
+        } => RelayEvent::OutboundCircuit {
+            relay_peer_id: relay_peer_id.to_string(),
+            limit: limit.map(|l| CircuitLimit::new(l.duration(), l.data_in_bytes())),
+        },
+        relay::client::Event::InboundCircuitEstablished { src_peer_id, limit } => {
+            RelayEvent::InboundCircuit {
+                src_peer_id: src_peer_id.to_string(),
+                limit: limit.map(|l| CircuitLimit::new(l.duration(), l.data_in_bytes())),
+            }
+        }
+    };
+    NodeEvent::Relay(event)
+}
@@ -0,0 +1,195 @@
+use anyhow::{anyhow, Result};
+use libp2p::rendezvous::{self, Cookie, ErrorCode, Namespace};
+use serde::Serialize;
+
+use super::NodeEvent;
+
+/// 服务端接受的最短 TTL（秒）。libp2p 默认要求至少 2 小时，实验时太长
+const SERVER_MIN_TTL: rendezvous::Ttl = 60;
+
+pub fn new_server() -> rendezvous::server::Behaviour {
+    rendezvous::server::Behaviour::new(
+        rendezvous::server::Config::default().with_min_ttl(SERVER_MIN_TTL),
+    )
+}
+
+pub fn namespace(value: String) -> Result<Namespace> {
+    Namespace::new(value).map_err(|_| {
+        anyhow!(
+            "Namespace is longer than {} bytes",
+            rendezvous::MAX_NAMESPACE
+        )
+    })
+}
+
+/// 分页 cookie 以十六进制在前端往返
+pub fn decode_cookie(cookie: &str) -> Result<Cookie> {
+    hex::decode(cookie.trim())
+        .ok()
+        .and_then(|bytes| Cookie::from_wire_encoding(bytes).ok())
+        .ok_or_else(|| anyhow!("Invalid rendezvous cookie"))
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Registration {
+    pub peer_id: String,
+    pub namespace: String,
+    pub addrs: Vec<String>,
+    /// 剩余有效期（秒）
+    pub ttl: u64,
+}
+
+impl From<rendezvous::Registration> for Registration {
+    fn from(registration: rendezvous::Registration) -> Self {
+        Self {
+            peer_id: registration.record.peer_id().to_string(),
+            namespace: registration.namespace.to_string(),
+            addrs: registration
+                .record
+                .addresses()
+                .iter()
+                .map(|a| a.to_string())
+                .collect(),
+            ttl: registration.ttl,
+        }
+    }
+}
+
+/// rendezvous 客户端与服务端事件
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum RendezvousEvent {
+    #[serde(rename_all = "camelCase")]
+    Registered {
+        rendezvous_node: String,
+        namespace: String,
+        ttl: u64,
+    },
+    #[serde(rename_all = "camelCase")]
+    RegisterFailed {
+        rendezvous_node: String,
+        namespace: String,
+        error: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    Discovered {
+        rendezvous_node: String,
+        registrations: Vec<Registration>,
+        /// 下次发现时带上，只返回新增的注册
+        cookie: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    DiscoverFailed {
+        rendezvous_node: String,
+        namespace: Option<String>,
+        error: String,
+    },
+    /// 之前发现的节点注册已过期
+    #[serde(rename_all = "camelCase")]
+    Expired { peer_id: String },
+    /// 以下为服务端事件
+    #[serde(rename_all = "camelCase")]
+    PeerRegistered { registration: Registration },
+    #[serde(rename_all = "camelCase")]
+    PeerNotRegistered {
+        peer_id: String,
+        namespace: String,
+        error: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    PeerUnregistered { peer_id: String, namespace: String },
+    #[serde(rename_all = "camelCase")]
+    RegistrationExpired { registration: Registration },
+    #[serde(rename_all = "camelCase")]
+    DiscoverServed { peer_id: String, count: usize },
+    #[serde(rename_all = "camelCase")]
+    DiscoverNotServed { peer_id: String, error: String },
+}
+
+fn error_message(error: ErrorCode) -> String {
+    format!("{:?}", error)
+}
+
+pub fn map_client_event(event: rendezvous::client::Event) -> NodeEvent {
+    use rendezvous::client::Event;
+    let event = match event {
+        Event::Registered {
+            rendezvous_node,
+            ttl,
+            namespace,
+        } => RendezvousEvent::Registered {
+            rendezvous_node: rendezvous_node.to_string(),
+            namespace: namespace.to_string(),
+            ttl,
+        },
+        Event::RegisterFailed {
+            rendezvous_node,
+            namespace,
+            error,
+        } => RendezvousEvent::RegisterFailed {
+            rendezvous_node: rendezvous_node.to_string(),
+            namespace: namespace.to_string(),
+            error: error_message(error),
+        },
+        Event::Discovered {
+            rendezvous_node,
+            registrations,
+            cookie,
+        } => RendezvousEvent::Discovered {
+            rendezvous_node: rendezvous_node.to_string(),
+            registrations: registrations.into_iter().map(Into::into).collect(),
+            cookie: hex::encode(cookie.into_wire_encoding()),
+        },
+        Event::DiscoverFailed {
+            rendezvous_node,
+            namespace,
+            error,
+        } => RendezvousEvent::DiscoverFailed {
+            rendezvous_node: rendezvous_node.to_string(),
+            namespace: namespace.map(|ns| ns.to_string()),
+            error: error_message(error),
+        },
+        Event::Expired { peer } => RendezvousEvent::Expired {
+            peer_id: peer.to_string(),
+        },
+    };
+    NodeEvent::Rendezvous(event)
+}
+
+pub fn map_server_event(event: rendezvous::server::Event) -> NodeEvent {
+    use rendezvous::server::Event;
+    let event = match event {
+        Event::DiscoverServed {
+            enquirer,
+            registrations,
+        } => RendezvousEvent::DiscoverServed {
+            peer_id: enquirer.to_string(),
+            count: registrations.len(),
+        },
+        Event::DiscoverNotServed { enquirer, error } => RendezvousEvent::DiscoverNotServed {
+            peer_id: enquirer.to_string(),
+            error: error_message(error),
+        },
+        Event::PeerRegistered { registration, .. } => RendezvousEvent::PeerRegistered {
+            registration: registration.into(),
+        },
+        Event::PeerNotRegistered {
+            peer,
+            namespace,
+            error,
+        } => RendezvousEvent::PeerNotRegistered {
+            peer_id: peer.to_string(),
+            namespace: namespace.to_string(),
+            error: error_message(error),
+        },
+        Event::PeerUnregistered { peer, namespace } => RendezvousEvent::PeerUnregistered {
+            peer_id: peer.to_string(),
+            namespace: namespace.to_string(),
+        },
+        Event::RegistrationExpired(registration) => RendezvousEvent::RegistrationExpired {
+            registration: registration.into(),
+        },
+    };
+    NodeEvent::Rendezvous(event)
+}
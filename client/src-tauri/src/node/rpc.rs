@@ -0,0 +1,397 @@
+use std::{collections::HashMap, io, time::Duration};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use libp2p::{
+    futures::{AsyncRead, AsyncWrite},
+    request_response::{
+        self, cbor, json, Message, OutboundRequestId, ProtocolSupport, ResponseChannel,
+    },
+    StreamProtocol,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::{sync::oneshot, time::Instant};
+
+use super::NodeEvent;
+
+/// 请求与响应在线上的编码，两端必须一致
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RpcFormat {
+    #[default]
+    Json,
+    Cbor,
+}
+
+/// 收到请求后的自动应答方式
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum Responder {
+    /// 原样返回请求
+    Echo,
+    /// 把请求中的字符串（含嵌套的）转为大写
+    Uppercase,
+    /// 等待 `ms` 毫秒后原样返回，超过请求方的超时即可观察到超时
+    Delay { ms: u64 },
+    /// 返回错误响应
+    Error { message: String },
+}
+
+/// 请求-响应协议参数，每个节点只运行一个协议
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RpcConfig {
+    /// 协议名，必须以 `/` 开头，双方一致才能协商成功
+    pub protocol: String,
+    pub format: RpcFormat,
+    /// 请求超时上限（秒），单个请求可以指定更短的超时
+    pub timeout_secs: u64,
+    pub responder: Responder,
+}
+
+impl Default for RpcConfig {
+    fn default() -> Self {
+        Self {
+            protocol: "/swarmbook/echo/1.0.0".to_string(),
+            format: RpcFormat::Json,
+            timeout_secs: 10,
+            responder: Responder::Echo,
+        }
+    }
+}
+
+/// 线上的响应。请求是任意 JSON 值，CBOR 编码时同样以 JSON 值的形式收发
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "status")]
+pub enum RpcResponse {
+    Ok { body: Value },
+    Error { message: String },
+}
+
+/// 按 [`RpcFormat`] 选择 libp2p 自带的 JSON 或 CBOR 编解码器，二者都限制了消息大小
+#[derive(Clone)]
+pub enum RpcCodec {
+    Json(json::codec::Codec<Value, RpcResponse>),
+    Cbor(cbor::codec::Codec<Value, RpcResponse>),
+}
+
+#[async_trait]
+impl request_response::Codec for RpcCodec {
+    type Protocol = StreamProtocol;
+    type Request = Value;
+    type Response = RpcResponse;
+
+    async fn read_request<T>(&mut self, protocol: &StreamProtocol, io: &mut T) -> io::Result<Value>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        match self {
+            RpcCodec::Json(codec) => codec.read_request(protocol, io).await,
+            RpcCodec::Cbor(codec) => codec.read_request(protocol, io).await,
+        }
+    }
+
+    async fn read_response<T>(
+        &mut self,
+        protocol: &StreamProtocol,
+        io: &mut T,
+    ) -> io::Result<RpcResponse>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        match self {
+            RpcCodec::Json(codec) => codec.read_response(protocol, io).await,
+            RpcCodec::Cbor(codec) => codec.read_response(protocol, io).await,
+        }
+    }
+
+    async fn write_request<T>(
+        &mut self,
+        protocol: &StreamProtocol,
+        io: &mut T,
+        request: Value,
+    ) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        match self {
+            RpcCodec::Json(codec) => codec.write_request(protocol, io, request).await,
+            RpcCodec::Cbor(codec) => codec.write_request(protocol, io, request).await,
+        }
+    }
+
+    async fn write_response<T>(
+        &mut self,
+        protocol: &StreamProtocol,
+        io: &mut T,
+        response: RpcResponse,
+    ) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        match self {
+            RpcCodec::Json(codec) => codec.write_response(protocol, io, response).await,
+            RpcCodec::Cbor(codec) => codec.write_response(protocol, io, response).await,
+        }
+    }
+}
+
+pub fn new_behaviour(
+    config: &RpcConfig,
+) -> Result<request_response::Behaviour<RpcCodec>, Box<dyn std::error::Error + Send + Sync>> {
+    let protocol = StreamProtocol::try_from_owned(config.protocol.trim().to_string())
+        .map_err(|e| anyhow!("Invalid request-response protocol: {}", e))?;
+    // 零时长的超时会让每个请求立即失败
+    if config.timeout_secs == 0 {
+        return Err(anyhow!("Request-response timeout must be at least 1 second").into());
+    }
+    let codec = match config.format {
+        RpcFormat::Json => RpcCodec::Json(Default::default()),
+        RpcFormat::Cbor => RpcCodec::Cbor(Default::default()),
+    };
+    Ok(request_response::Behaviour::with_codec(
+        codec,
+        [(protocol, ProtocolSupport::Full)],
+        request_response::Config::default()
+            .with_request_timeout(Duration::from_secs(config.timeout_secs)),
+    ))
+}
+
+/// 请求方拿到的结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcReply {
+    pub peer_id: String,
+    pub request_id: String,
+    pub response: RpcResponse,
+    pub rtt_ms: u128,
+}
+
+/// 请求-响应事件，发出的请求的结果同时也会回给调用方
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum RpcEvent {
+    /// 收到请求，`responder` 是此时的应答方式
+    #[serde(rename_all = "camelCase")]
+    RequestReceived {
+        peer_id: String,
+        request_id: String,
+        request: Value,
+        responder: Responder,
+    },
+    #[serde(rename_all = "camelCase")]
+    ResponseSent { peer_id: String, request_id: String },
+    #[serde(rename_all = "camelCase")]
+    InboundFailed {
+        peer_id: String,
+        request_id: String,
+        error: String,
+    },
+    /// 调用方超时放弃后才到达的响应也会推送
+    #[serde(rename_all = "camelCase")]
+    ResponseReceived {
+        peer_id: String,
+        request_id: String,
+        response: RpcResponse,
+        rtt_ms: u128,
+    },
+    #[serde(rename_all = "camelCase")]
+    OutboundFailed {
+        peer_id: String,
+        request_id: String,
+        error: String,
+    },
+}
+
+struct Pending {
+    sent_at: Instant,
+    reply: oneshot::Sender<Result<RpcReply>>,
+}
+
+/// 延迟应答的请求
+struct Delayed {
+    at: Instant,
+    channel: ResponseChannel<RpcResponse>,
+    response: RpcResponse,
+}
+
+/// 应答方式、等待响应的调用方以及延迟应答队列
+pub struct Rpc {
+    responder: Responder,
+    pending: HashMap<OutboundRequestId, Pending>,
+    delayed: Vec<Delayed>,
+}
+
+impl Rpc {
+    pub fn new(responder: Responder) -> Self {
+        Self {
+            responder,
+            pending: HashMap::new(),
+            delayed: Vec::new(),
+        }
+    }
+
+    pub fn set_responder(&mut self, responder: Responder) {
+        self.responder = responder;
+    }
+
+    pub fn sent(
+        &mut self,
+        request_id: OutboundRequestId,
+        reply: oneshot::Sender<Result<RpcReply>>,
+    ) {
+        self.pending.insert(
+            request_id,
+            Pending {
+                sent_at: Instant::now(),
+                reply,
+            },
+        );
+    }
+
+    /// 按当前应答方式回应请求，并把发出请求的结果交给等待的调用方
+    pub fn handle_event(
+        &mut self,
+        behaviour: &mut request_response::Behaviour<RpcCodec>,
+        event: request_response::Event<Value, RpcResponse>,
+    ) -> NodeEvent {
+        let event = match event {
+            request_response::Event::Message {
+                peer,
+                message:
+                    Message::Request {
+                        request_id,
+                        request,
+                        channel,
+                    },
+                ..
+            } => {
+                let response = self.respond(&request);
+                if let Responder::Delay { ms } = self.responder {
+                    self.delayed.push(Delayed {
+                        at: Instant::now() + Duration::from_millis(ms),
+                        channel,
+                        response,
+                    });
+                } else {
+                    // 连接已断开时会另有 InboundFailure 事件
+                    let _ = behaviour.send_response(channel, response);
+                }
+                RpcEvent::RequestReceived {
+                    peer_id: peer.to_string(),
+                    request_id: request_id.to_string(),
+                    request,
+                    responder: self.responder.clone(),
+                }
+            }
+            request_response::Event::Message {
+                peer,
+                message:
+                    Message::Response {
+                        request_id,
+                        response,
+                    },
+                ..
+            } => {
+                let rtt_ms = self.pending.remove(&request_id).map_or(0, |pending| {
+                    let rtt_ms = pending.sent_at.elapsed().as_millis();
+                    let _ = pending.reply.send(Ok(RpcReply {
+                        peer_id: peer.to_string(),
+                        request_id: request_id.to_string(),
+                        response: response.clone(),
+                        rtt_ms,
+                    }));
+                    rtt_ms
+                });
+                RpcEvent::ResponseReceived {
+                    peer_id: peer.to_string(),
+                    request_id: request_id.to_string(),
+                    response,
+                    rtt_ms,
+                }
+            }
+            request_response::Event::OutboundFailure {
+                peer,
+                request_id,
+                error,
+                ..
+            } => {
+                let error = error.to_string();
+                if let Some(pending) = self.pending.remove(&request_id) {
+                    let _ = pending.reply.send(Err(anyhow!(
+                        "Request {} to {} failed: {}",
+                        request_id,
+                        peer,
+                        error
+                    )));
+                }
+                RpcEvent::OutboundFailed {
+                    peer_id: peer.to_string(),
+                    request_id: request_id.to_string(),
+                    error,
+                }
+            }
+            request_response::Event::InboundFailure {
+                peer,
+                request_id,
+                error,
+                ..
+            } => RpcEvent::InboundFailed {
+                peer_id: peer.to_string(),
+                request_id: request_id.to_string(),
+                error: error.to_string(),
+            },
+            request_response::Event::ResponseSent {
+                peer, request_id, ..
+            } => RpcEvent::ResponseSent {
+                peer_id: peer.to_string(),
+                request_id: request_id.to_string(),
+            },
+        };
+        NodeEvent::Rpc(event)
+    }
+
+    fn respond(&self, request: &Value) -> RpcResponse {
+        match &self.responder {
+            Responder::Echo | Responder::Delay { .. } => RpcResponse::Ok {
+                body: request.clone(),
+            },
+            Responder::Uppercase => RpcResponse::Ok {
+                body: uppercase(request),
+            },
+            Responder::Error { message } => RpcResponse::Error {
+                message: message.clone(),
+            },
+        }
+    }
+
+    pub fn next_reply(&self) -> Option<Instant> {
+        self.delayed.iter().map(|delayed| delayed.at).min()
+    }
+
+    /// 发送到期的延迟应答
+    pub fn send_due(&mut self, behaviour: &mut request_response::Behaviour<RpcCodec>) {
+        let now = Instant::now();
+        let (due, waiting): (Vec<_>, Vec<_>) = std::mem::take(&mut self.delayed)
+            .into_iter()
+            .partition(|delayed| delayed.at <= now);
+        self.delayed = waiting;
+        for delayed in due {
+            let _ = behaviour.send_response(delayed.channel, delayed.response);
+        }
+    }
+}
+
+fn uppercase(value: &Value) -> Value {
+    match value {
+        Value::String(s) => Value::String(s.to_uppercase()),
+        Value::Array(items) => Value::Array(items.iter().map(uppercase).collect()),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| (key.clone(), uppercase(value)))
+                .collect(),
+        ),
+        value => value.clone(),
+    }
+}
@@ -0,0 +1,398 @@
+use std::{
+    collections::{HashMap, HashSet},
+    net::IpAddr,
+    time::Duration,
+};
+
+use libp2p::{
+    gossipsub::{self, PeerScoreParams, PeerScoreThresholds, TopicHash, TopicScoreParams},
+    multiaddr::Protocol,
+    swarm::ConnectionId,
+    Multiaddr, PeerId,
+};
+use serde::{Deserialize, Serialize};
+use tokio::time::Instant;
+
+/// gossipsub 评分参数，节点启动时生效。
+/// 缺省值针对消息很少的实验网络：关闭 mesh 投递不足惩罚（P3），
+/// 加重并放慢衰减无效消息惩罚（P4），几条被拒绝的消息就足以让对方进入灰名单
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ScoreConfig {
+    /// 低于该分数不再与其交换 gossip
+    pub gossip_threshold: f64,
+    /// 低于该分数不再向其发布自己的消息
+    pub publish_threshold: f64,
+    /// 低于该分数忽略其全部 RPC
+    pub graylist_threshold: f64,
+    /// 高于该分数才接受其 PRUNE 中附带的节点交换（PX）
+    pub accept_px_threshold: f64,
+    /// mesh 中位数低于该分数时尝试嫁接高分节点
+    pub opportunistic_graft_threshold: f64,
+    /// 所有话题得分之和的上限
+    pub topic_score_cap: f64,
+    /// P6：同一 IP 上的节点数超过阈值后，按超出数的平方乘以权重
+    pub ip_colocation_factor_weight: f64,
+    pub ip_colocation_factor_threshold: f64,
+    /// P7：协议违规（如重复 GRAFT）计数的平方乘以权重
+    pub behaviour_penalty_weight: f64,
+    pub behaviour_penalty_decay: f64,
+    /// 计数器衰减间隔（毫秒）
+    pub decay_interval_ms: u64,
+    /// 推送评分明细的间隔（秒），0 表示不推送
+    pub report_interval_secs: u64,
+    /// 每个已订阅话题使用的参数
+    pub topic: TopicScoreConfig,
+}
+
+impl Default for ScoreConfig {
+    fn default() -> Self {
+        let thresholds = PeerScoreThresholds::default();
+        let params = PeerScoreParams::default();
+        Self {
+            gossip_threshold: thresholds.gossip_threshold,
+            publish_threshold: thresholds.publish_threshold,
+            graylist_threshold: thresholds.graylist_threshold,
+            accept_px_threshold: thresholds.accept_px_threshold,
+            opportunistic_graft_threshold: thresholds.opportunistic_graft_threshold,
+            topic_score_cap: params.topic_score_cap,
+            ip_colocation_factor_weight: params.ip_colocation_factor_weight,
+            ip_colocation_factor_threshold: params.ip_colocation_factor_threshold,
+            behaviour_penalty_weight: params.behaviour_penalty_weight,
+            behaviour_penalty_decay: params.behaviour_penalty_decay,
+            decay_interval_ms: params.decay_interval.as_millis() as u64,
+            report_interval_secs: 5,
+            topic: TopicScoreConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TopicScoreConfig {
+    pub topic_weight: f64,
+    /// P1：在 mesh 中每满 `time_in_mesh_quantum_ms` 计 1，最多计 `time_in_mesh_cap`
+    pub time_in_mesh_weight: f64,
+    pub time_in_mesh_quantum_ms: u64,
+    pub time_in_mesh_cap: f64,
+    /// P2：第一个送达消息的次数
+    pub first_message_deliveries_weight: f64,
+    pub first_message_deliveries_decay: f64,
+    pub first_message_deliveries_cap: f64,
+    /// P3：mesh 内投递少于阈值时按差额的平方惩罚，权重为 0 时关闭（同时关闭 P3b）
+    pub mesh_message_deliveries_weight: f64,
+    pub mesh_message_deliveries_threshold: f64,
+    /// P4：未通过校验的消息数的平方乘以权重
+    pub invalid_message_deliveries_weight: f64,
+    pub invalid_message_deliveries_decay: f64,
+}
+
+impl Default for TopicScoreConfig {
+    fn default() -> Self {
+        Self {
+            topic_weight: 1.0,
+            time_in_mesh_weight: 0.1,
+            time_in_mesh_quantum_ms: 1000,
+            time_in_mesh_cap: 60.0,
+            first_message_deliveries_weight: 1.0,
+            first_message_deliveries_decay: 0.5,
+            first_message_deliveries_cap: 20.0,
+            mesh_message_deliveries_weight: 0.0,
+            mesh_message_deliveries_threshold: 20.0,
+            invalid_message_deliveries_weight: -10.0,
+            invalid_message_deliveries_decay: 0.9,
+        }
+    }
+}
+
+impl ScoreConfig {
+    pub fn params(&self) -> PeerScoreParams {
+        PeerScoreParams {
+            topic_score_cap: self.topic_score_cap,
+            ip_colocation_factor_weight: self.ip_colocation_factor_weight,
+            ip_colocation_factor_threshold: self.ip_colocation_factor_threshold,
+            behaviour_penalty_weight: self.behaviour_penalty_weight,
+            behaviour_penalty_decay: self.behaviour_penalty_decay,
+            decay_interval: self.decay_interval(),
+            ..PeerScoreParams::default()
+        }
+    }
+
+    pub fn thresholds(&self) -> PeerScoreThresholds {
+        PeerScoreThresholds {
+            gossip_threshold: self.gossip_threshold,
+            publish_threshold: self.publish_threshold,
+            graylist_threshold: self.graylist_threshold,
+            accept_px_threshold: self.accept_px_threshold,
+            opportunistic_graft_threshold: self.opportunistic_graft_threshold,
+        }
+    }
+
+    pub fn topic_params(&self) -> TopicScoreParams {
+        let topic = &self.topic;
+        TopicScoreParams {
+            topic_weight: topic.topic_weight,
+            time_in_mesh_weight: topic.time_in_mesh_weight,
+            time_in_mesh_quantum: Duration::from_millis(topic.time_in_mesh_quantum_ms),
+            time_in_mesh_cap: topic.time_in_mesh_cap,
+            first_message_deliveries_weight: topic.first_message_deliveries_weight,
+            first_message_deliveries_decay: topic.first_message_deliveries_decay,
+            first_message_deliveries_cap: topic.first_message_deliveries_cap,
+            mesh_message_deliveries_weight: topic.mesh_message_deliveries_weight,
+            mesh_message_deliveries_threshold: topic.mesh_message_deliveries_threshold,
+            mesh_failure_penalty_weight: topic.mesh_message_deliveries_weight,
+            invalid_message_deliveries_weight: topic.invalid_message_deliveries_weight,
+            invalid_message_deliveries_decay: topic.invalid_message_deliveries_decay,
+            ..TopicScoreParams::default()
+        }
+    }
+
+    pub fn decay_interval(&self) -> Duration {
+        Duration::from_millis(self.decay_interval_ms)
+    }
+}
+
+/// 单个节点的评分明细
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerScore {
+    pub peer_id: String,
+    /// libp2p 计算的总分，与各阈值比较决定是否 gossip、发布或拉入灰名单
+    pub score: f64,
+    pub topics: Vec<TopicScore>,
+    /// P6 IP 共置惩罚
+    pub ip_colocation: f64,
+    /// 总分中没有单独列出的部分，主要是 P3 投递不足、P7 行为惩罚和话题分上限
+    pub other: f64,
+}
+
+/// 节点在单个话题上的得分，`*_score` 已乘上话题权重
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TopicScore {
+    pub topic: String,
+    pub in_mesh: bool,
+    pub mesh_time_ms: u128,
+    pub time_in_mesh_score: f64,
+    /// 衰减后的计数
+    pub first_message_deliveries: f64,
+    pub first_message_deliveries_score: f64,
+    pub invalid_message_deliveries: f64,
+    pub invalid_message_deliveries_score: f64,
+}
+
+#[derive(Default)]
+struct TopicStats {
+    grafted_at: Option<Instant>,
+    first_message_deliveries: f64,
+    invalid_message_deliveries: f64,
+}
+
+/// libp2p 不公开评分的各个分量，这里根据同样的事件和参数复算 P1、P2、P4 与 P6，
+/// 总分仍以 libp2p 为准，差额归入 [`PeerScore::other`]
+pub struct ScoreTracker {
+    config: ScoreConfig,
+    peers: HashMap<PeerId, HashMap<TopicHash, TopicStats>>,
+    /// 连接 -> 对方节点和 IP，用于计算 IP 共置
+    ips: HashMap<ConnectionId, (PeerId, IpAddr)>,
+    last_report: Instant,
+}
+
+impl ScoreTracker {
+    pub fn new(config: ScoreConfig) -> Self {
+        Self {
+            config,
+            peers: HashMap::new(),
+            ips: HashMap::new(),
+            last_report: Instant::now(),
+        }
+    }
+
+    pub fn config(&self) -> &ScoreConfig {
+        &self.config
+    }
+
+    pub fn connection_established(
+        &mut self,
+        connection_id: ConnectionId,
+        peer_id: PeerId,
+        addr: &Multiaddr,
+    ) {
+        let ip = addr.iter().find_map(|p| match p {
+            Protocol::Ip4(ip) => Some(IpAddr::V4(ip)),
+            Protocol::Ip6(ip) => Some(IpAddr::V6(ip)),
+            _ => None,
+        });
+        if let Some(ip) = ip {
+            self.ips.insert(connection_id, (peer_id, ip));
+        }
+    }
+
+    pub fn connection_closed(&mut self, connection_id: ConnectionId) {
+        self.ips.remove(&connection_id);
+    }
+
+    /// 通过校验的消息，计入第一个送达者的 P2
+    pub fn delivered(&mut self, peer_id: PeerId, topic: &TopicHash) {
+        let cap = self.config.topic.first_message_deliveries_cap;
+        let stats = self.stats(peer_id, topic);
+        stats.first_message_deliveries = (stats.first_message_deliveries + 1.0).min(cap);
+    }
+
+    /// 被拒绝的消息，计入转发者的 P4
+    pub fn rejected(&mut self, peer_id: PeerId, topic: &TopicHash) {
+        self.stats(peer_id, topic).invalid_message_deliveries += 1.0;
+    }
+
+    /// 每个衰减周期调用一次：衰减计数器并根据当前 mesh 更新嫁接时间
+    pub fn refresh(&mut self, gossipsub: &gossipsub::Behaviour) {
+        let decay_to_zero = PeerScoreParams::default().decay_to_zero;
+        let topic = &self.config.topic;
+        let decay = |value: &mut f64, factor: f64| {
+            *value *= factor;
+            if *value < decay_to_zero {
+                *value = 0.0;
+            }
+        };
+
+        let now = Instant::now();
+        let connected: HashSet<_> = gossipsub.all_peers().map(|(p, _)| *p).collect();
+        self.peers.retain(|peer_id, _| connected.contains(peer_id));
+        for (peer_id, topics) in self.peers.iter_mut() {
+            for (topic_hash, stats) in topics.iter_mut() {
+                decay(
+                    &mut stats.first_message_deliveries,
+                    topic.first_message_deliveries_decay,
+                );
+                decay(
+                    &mut stats.invalid_message_deliveries,
+                    topic.invalid_message_deliveries_decay,
+                );
+                if !gossipsub.mesh_peers(topic_hash).any(|p| p == peer_id) {
+                    stats.grafted_at = None;
+                }
+            }
+        }
+        let meshes: Vec<_> = gossipsub
+            .topics()
+            .flat_map(|topic| gossipsub.mesh_peers(topic).map(|p| (*p, topic.clone())))
+            .collect();
+        for (peer_id, topic_hash) in meshes {
+            self.stats(peer_id, &topic_hash)
+                .grafted_at
+                .get_or_insert(now);
+        }
+    }
+
+    /// 距离上次推送已超过推送间隔
+    pub fn report_due(&mut self) -> bool {
+        let interval = self.config.report_interval_secs;
+        if interval == 0 || self.last_report.elapsed() < Duration::from_secs(interval) {
+            return false;
+        }
+        self.last_report = Instant::now();
+        true
+    }
+
+    pub fn report(&self, gossipsub: &gossipsub::Behaviour) -> Vec<PeerScore> {
+        let mut peers_per_ip: HashMap<IpAddr, HashSet<PeerId>> = HashMap::new();
+        for (peer_id, ip) in self.ips.values() {
+            peers_per_ip.entry(*ip).or_default().insert(*peer_id);
+        }
+
+        let mut report: Vec<_> = gossipsub
+            .all_peers()
+            .map(|(peer_id, _)| {
+                let topics = self.topic_scores(peer_id);
+                let topic_total: f64 = topics
+                    .iter()
+                    .map(|t| {
+                        t.time_in_mesh_score
+                            + t.first_message_deliveries_score
+                            + t.invalid_message_deliveries_score
+                    })
+                    .sum();
+                let topic_total = if self.config.topic_score_cap > 0.0 {
+                    topic_total.min(self.config.topic_score_cap)
+                } else {
+                    topic_total
+                };
+                let ips: HashSet<_> = self
+                    .ips
+                    .values()
+                    .filter(|(p, _)| p == peer_id)
+                    .map(|(_, ip)| *ip)
+                    .collect();
+                let ip_colocation: f64 = ips
+                    .iter()
+                    .map(|ip| {
+                        let surplus = peers_per_ip.get(ip).map_or(0, |peers| peers.len()) as f64
+                            - self.config.ip_colocation_factor_threshold;
+                        if surplus > 0.0 {
+                            surplus * surplus * self.config.ip_colocation_factor_weight
+                        } else {
+                            0.0
+                        }
+                    })
+                    .sum();
+                let score = gossipsub.peer_score(peer_id).unwrap_or_default();
+                PeerScore {
+                    peer_id: peer_id.to_string(),
+                    score,
+                    topics,
+                    ip_colocation,
+                    other: score - topic_total - ip_colocation,
+                }
+            })
+            .collect();
+        report.sort_by(|a, b| b.score.total_cmp(&a.score));
+        report
+    }
+
+    fn topic_scores(&self, peer_id: &PeerId) -> Vec<TopicScore> {
+        let topic = &self.config.topic;
+        let quantum = Duration::from_millis(topic.time_in_mesh_quantum_ms).as_secs_f64();
+        let mut scores: Vec<_> = self
+            .peers
+            .get(peer_id)
+            .into_iter()
+            .flatten()
+            .map(|(topic_hash, stats)| {
+                let mesh_time = stats.grafted_at.map(|at| at.elapsed()).unwrap_or_default();
+                let p1 = if stats.grafted_at.is_some() {
+                    (mesh_time.as_secs_f64() / quantum).min(topic.time_in_mesh_cap)
+                } else {
+                    0.0
+                };
+                let p2 = stats
+                    .first_message_deliveries
+                    .min(topic.first_message_deliveries_cap);
+                let p4 = stats.invalid_message_deliveries * stats.invalid_message_deliveries;
+                TopicScore {
+                    topic: topic_hash.to_string(),
+                    in_mesh: stats.grafted_at.is_some(),
+                    mesh_time_ms: mesh_time.as_millis(),
+                    time_in_mesh_score: p1 * topic.time_in_mesh_weight * topic.topic_weight,
+                    first_message_deliveries: stats.first_message_deliveries,
+                    first_message_deliveries_score: p2
+                        * topic.first_message_deliveries_weight
+                        * topic.topic_weight,
+                    invalid_message_deliveries: stats.invalid_message_deliveries,
+                    invalid_message_deliveries_score: p4
+                        * topic.invalid_message_deliveries_weight
+                        * topic.topic_weight,
+                }
+            })
+            .collect();
+        scores.sort_by(|a, b| a.topic.cmp(&b.topic));
+        scores
+    }
+
+    fn stats(&mut self, peer_id: PeerId, topic: &TopicHash) -> &mut TopicStats {
+        self.peers
+            .entry(peer_id)
+            .or_default()
+            .entry(topic.clone())
+            .or_default()
+    }
+}
@@ -0,0 +1,545 @@
+use std::{
+    collections::HashMap,
+    io,
+    sync::{Arc, Mutex as StdMutex},
+    time::Duration,
+};
+
+use anyhow::{anyhow, bail, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use libp2p::{
+    futures::{
+        io::{ReadHalf, WriteHalf},
+        AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, StreamExt,
+    },
+    PeerId, Stream, StreamProtocol,
+};
+use libp2p_stream::{Control, OpenStreamError};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    sync::{broadcast, Mutex},
+    task::JoinHandle,
+    time,
+};
+
+use super::NodeEvent;
+
+/// 打开流（含拨号和 multistream-select 协商）的最长等待时间
+const OPEN_TIMEOUT: Duration = Duration::from_secs(10);
+/// 长度前缀帧的上限
+const MAX_FRAME_LEN: usize = 1024 * 1024;
+/// 不分帧时每次读取的最大字节数
+const READ_CHUNK: usize = 64 * 1024;
+
+/// 流上的分帧方式
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Framing {
+    /// 不分帧，读到多少推送多少
+    #[default]
+    Raw,
+    /// unsigned varint 长度前缀，与 libp2p 自带协议的分帧方式相同
+    LengthPrefixed,
+}
+
+/// 前端收发数据时使用的编码
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Encoding {
+    /// 收到非 UTF-8 数据时按有损方式解码
+    #[default]
+    Utf8,
+    Hex,
+    Base64,
+}
+
+impl Encoding {
+    fn decode(self, data: &str) -> Result<Vec<u8>> {
+        Ok(match self {
+            Encoding::Utf8 => data.as_bytes().to_vec(),
+            Encoding::Hex => hex::decode(data.trim()).map_err(|_| anyhow!("Invalid hex data"))?,
+            Encoding::Base64 => STANDARD
+                .decode(data.trim())
+                .map_err(|_| anyhow!("Invalid base64 data"))?,
+        })
+    }
+
+    fn encode(self, data: &[u8]) -> String {
+        match self {
+            Encoding::Utf8 => String::from_utf8_lossy(data).into_owned(),
+            Encoding::Hex => hex::encode(data),
+            Encoding::Base64 => STANDARD.encode(data),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct StreamOptions {
+    pub framing: Framing,
+    pub encoding: Encoding,
+}
+
+/// 入站流的处理方式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum StreamHandler {
+    /// 交给前端读写，与主动打开的流相同
+    Manual,
+    /// 把收到的每一帧原样写回，对方关闭写端后随之关闭
+    Echo,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StreamDirection {
+    Outbound,
+    Inbound,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamInfo {
+    pub stream_id: u64,
+    pub peer_id: String,
+    pub protocol: String,
+    pub direction: StreamDirection,
+    pub options: StreamOptions,
+    /// 入站流的处理方式
+    pub handler: Option<StreamHandler>,
+}
+
+/// 已注册的入站协议
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AcceptHandler {
+    pub protocol: String,
+    pub handler: StreamHandler,
+    pub options: StreamOptions,
+}
+
+/// 打开的流和已注册的入站协议
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamsSnapshot {
+    pub streams: Vec<StreamInfo>,
+    pub handlers: Vec<AcceptHandler>,
+}
+
+/// 原始流事件
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum StreamEvent {
+    /// 协商完成，入站流由已注册的处理方式接管
+    Opened { stream: StreamInfo },
+    /// 收到一帧数据，`len` 为原始字节数
+    #[serde(rename_all = "camelCase")]
+    Data {
+        stream_id: u64,
+        data: String,
+        len: usize,
+    },
+    /// 对方关闭了写端，本地仍可继续写
+    #[serde(rename_all = "camelCase")]
+    RemoteClosed { stream_id: u64 },
+    /// 双方都已关闭或读写出错，`error` 为空表示正常关闭
+    #[serde(rename_all = "camelCase")]
+    Closed {
+        stream_id: u64,
+        error: Option<String>,
+    },
+}
+
+struct Entry {
+    info: StreamInfo,
+    writer: Arc<Mutex<WriteHalf<Stream>>>,
+    write_closed: bool,
+    read_closed: bool,
+    reader: JoinHandle<()>,
+}
+
+struct Accepting {
+    handler: AcceptHandler,
+    task: JoinHandle<()>,
+}
+
+#[derive(Default)]
+struct Table {
+    next_id: u64,
+    streams: HashMap<u64, Entry>,
+    accepting: HashMap<StreamProtocol, Accepting>,
+}
+
+/// 通过 libp2p-stream 在任意协议上收发原始字节。
+/// 流的读写不经过事件循环，读到的数据通过节点事件总线推送
+#[derive(Clone)]
+pub struct Streams {
+    control: Control,
+    events: broadcast::Sender<NodeEvent>,
+    table: Arc<StdMutex<Table>>,
+}
+
+impl Streams {
+    pub fn new(control: Control, events: broadcast::Sender<NodeEvent>) -> Self {
+        Self {
+            control,
+            events,
+            table: Arc::default(),
+        }
+    }
+
+    /// 打开到 `peer` 的流，未连接时会先拨号
+    pub async fn open(
+        &self,
+        peer: PeerId,
+        protocol: &str,
+        options: StreamOptions,
+    ) -> Result<StreamInfo> {
+        let protocol = parse_protocol(protocol)?;
+        let stream = time::timeout(
+            OPEN_TIMEOUT,
+            self.control.clone().open_stream(peer, protocol.clone()),
+        )
+        .await
+        .map_err(|_| anyhow!("Opening {} stream to {} timed out", protocol, peer))?
+        .map_err(|e| match e {
+            OpenStreamError::UnsupportedProtocol(protocol) => {
+                anyhow!("{} does not support {}", peer, protocol)
+            }
+            e => anyhow!("Failed to open stream to {}: {}", peer, e),
+        })?;
+        Ok(self.register(
+            peer,
+            protocol,
+            StreamDirection::Outbound,
+            stream,
+            options,
+            None,
+        ))
+    }
+
+    /// 写入一条数据，按流的编码解码后按分帧方式发送，返回写入的原始字节数
+    pub async fn write(&self, stream_id: u64, data: &str) -> Result<usize> {
+        let (writer, options) = {
+            let table = self.lock();
+            let entry = table
+                .streams
+                .get(&stream_id)
+                .ok_or_else(|| no_stream(stream_id))?;
+            if entry.write_closed {
+                bail!("Stream {} is closed for writing", stream_id);
+            }
+            (entry.writer.clone(), entry.info.options)
+        };
+        let data = options.encoding.decode(data)?;
+        write_frame(&mut *writer.lock().await, options.framing, &data)
+            .await
+            .map_err(|e| anyhow!("Failed to write to stream {}: {}", stream_id, e))?;
+        Ok(data.len())
+    }
+
+    /// 关闭写端，对方读到 EOF；仍会继续读取直到对方也关闭
+    pub async fn close(&self, stream_id: u64) -> Result<()> {
+        let writer = {
+            let mut table = self.lock();
+            let entry = table
+                .streams
+                .get_mut(&stream_id)
+                .ok_or_else(|| no_stream(stream_id))?;
+            if entry.write_closed {
+                return Ok(());
+            }
+            entry.write_closed = true;
+            entry.writer.clone()
+        };
+        let error = writer.lock().await.close().await.err();
+        match error {
+            Some(e) => self.finish(stream_id, Some(e.to_string())),
+            None => {
+                if self
+                    .lock()
+                    .streams
+                    .get(&stream_id)
+                    .is_some_and(|e| e.read_closed)
+                {
+                    self.finish(stream_id, None);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 在 `protocol` 上接受入站流
+    pub fn accept(
+        &self,
+        protocol: &str,
+        handler: StreamHandler,
+        options: StreamOptions,
+    ) -> Result<()> {
+        let protocol = parse_protocol(protocol)?;
+        let mut table = self.lock();
+        if table.accepting.contains_key(&protocol) {
+            bail!("Already accepting streams on {}", protocol);
+        }
+        let mut incoming = self
+            .control
+            .clone()
+            .accept(protocol.clone())
+            .map_err(|_| anyhow!("Already accepting streams on {}", protocol))?;
+        let streams = self.clone();
+        let stream_protocol = protocol.clone();
+        let task = tokio::spawn(async move {
+            while let Some((peer, stream)) = incoming.next().await {
+                streams.register(
+                    peer,
+                    stream_protocol.clone(),
+                    StreamDirection::Inbound,
+                    stream,
+                    options,
+                    Some(handler),
+                );
+            }
+        });
+        table.accepting.insert(
+            protocol.clone(),
+            Accepting {
+                handler: AcceptHandler {
+                    protocol: protocol.to_string(),
+                    handler,
+                    options,
+                },
+                task,
+            },
+        );
+        Ok(())
+    }
+
+    /// 不再接受新的入站流，已打开的流不受影响
+    pub fn stop_accepting(&self, protocol: &str) -> Result<()> {
+        let protocol = parse_protocol(protocol)?;
+        let accepting = self
+            .lock()
+            .accepting
+            .remove(&protocol)
+            .ok_or_else(|| anyhow!("Not accepting streams on {}", protocol))?;
+        accepting.task.abort();
+        Ok(())
+    }
+
+    pub fn snapshot(&self) -> StreamsSnapshot {
+        let table = self.lock();
+        let mut streams: Vec<_> = table.streams.values().map(|e| e.info.clone()).collect();
+        streams.sort_by_key(|info| info.stream_id);
+        let mut handlers: Vec<_> = table
+            .accepting
+            .values()
+            .map(|a| a.handler.clone())
+            .collect();
+        handlers.sort_by(|a, b| a.protocol.cmp(&b.protocol));
+        StreamsSnapshot { streams, handlers }
+    }
+
+    /// 节点停止时结束所有读取和接受任务
+    pub fn abort_all(&self) {
+        let mut table = self.lock();
+        for (_, entry) in table.streams.drain() {
+            entry.reader.abort();
+        }
+        for (_, accepting) in table.accepting.drain() {
+            accepting.task.abort();
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Table> {
+        self.table.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn register(
+        &self,
+        peer: PeerId,
+        protocol: StreamProtocol,
+        direction: StreamDirection,
+        stream: Stream,
+        options: StreamOptions,
+        handler: Option<StreamHandler>,
+    ) -> StreamInfo {
+        let (reader, writer) = stream.split();
+        let writer = Arc::new(Mutex::new(writer));
+        let echo = matches!(handler, Some(StreamHandler::Echo)).then(|| writer.clone());
+
+        // 持锁期间启动读取任务，保证它结束时能在表中找到自己，且数据事件在打开事件之后
+        let mut table = self.lock();
+        let stream_id = table.next_id;
+        table.next_id += 1;
+        let info = StreamInfo {
+            stream_id,
+            peer_id: peer.to_string(),
+            protocol: protocol.to_string(),
+            direction,
+            options,
+            handler,
+        };
+        self.emit(StreamEvent::Opened {
+            stream: info.clone(),
+        });
+        let reader = tokio::spawn(self.clone().read(stream_id, reader, options, echo));
+        table.streams.insert(
+            stream_id,
+            Entry {
+                info: info.clone(),
+                writer,
+                write_closed: false,
+                read_closed: false,
+                reader,
+            },
+        );
+        info
+    }
+
+    async fn read(
+        self,
+        stream_id: u64,
+        mut reader: ReadHalf<Stream>,
+        options: StreamOptions,
+        echo: Option<Arc<Mutex<WriteHalf<Stream>>>>,
+    ) {
+        let mut buf = vec![0; READ_CHUNK];
+        let error = loop {
+            let frame = match read_frame(&mut reader, options.framing, &mut buf).await {
+                Ok(Some(frame)) => frame,
+                Ok(None) => break None,
+                Err(e) => break Some(e.to_string()),
+            };
+            self.emit(StreamEvent::Data {
+                stream_id,
+                data: options.encoding.encode(&frame),
+                len: frame.len(),
+            });
+            if let Some(writer) = &echo {
+                if let Err(e) =
+                    write_frame(&mut *writer.lock().await, options.framing, &frame).await
+                {
+                    break Some(e.to_string());
+                }
+            }
+        };
+        if error.is_some() {
+            self.finish(stream_id, error);
+            return;
+        }
+
+        self.emit(StreamEvent::RemoteClosed { stream_id });
+        let write_closed = {
+            let mut table = self.lock();
+            let Some(entry) = table.streams.get_mut(&stream_id) else {
+                return;
+            };
+            entry.read_closed = true;
+            entry.write_closed
+        };
+        if write_closed {
+            self.finish(stream_id, None);
+        } else if echo.is_some() {
+            let _ = self.close(stream_id).await;
+        }
+    }
+
+    /// 从表中移除流并推送关闭事件
+    fn finish(&self, stream_id: u64, error: Option<String>) {
+        let removed = self.lock().streams.remove(&stream_id);
+        if let Some(entry) = removed {
+            entry.reader.abort();
+            self.emit(StreamEvent::Closed { stream_id, error });
+        }
+    }
+
+    fn emit(&self, event: StreamEvent) {
+        let _ = self.events.send(NodeEvent::Stream(event));
+    }
+}
+
+fn parse_protocol(protocol: &str) -> Result<StreamProtocol> {
+    StreamProtocol::try_from_owned(protocol.trim().to_string())
+        .map_err(|e| anyhow!("Invalid protocol {}: {}", protocol.trim(), e))
+}
+
+fn no_stream(stream_id: u64) -> anyhow::Error {
+    anyhow!("Stream {} is not open", stream_id)
+}
+
+/// 读取一帧，对方在帧边界关闭写端时返回 `None`
+pub(super) async fn read_frame<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    framing: Framing,
+    buf: &mut [u8],
+) -> io::Result<Option<Vec<u8>>> {
+    match framing {
+        Framing::Raw => {
+            let n = reader.read(buf).await?;
+            Ok((n > 0).then(|| buf[..n].to_vec()))
+        }
+        Framing::LengthPrefixed => {
+            let mut len = 0usize;
+            for shift in (0..).step_by(7) {
+                if shift > 28 {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "frame length prefix is too long",
+                    ));
+                }
+                let mut byte = [0u8];
+                if reader.read(&mut byte).await? == 0 {
+                    if shift == 0 {
+                        return Ok(None);
+                    }
+                    return Err(io::ErrorKind::UnexpectedEof.into());
+                }
+                len |= ((byte[0] & 0x7f) as usize) << shift;
+                if byte[0] & 0x80 == 0 {
+                    break;
+                }
+            }
+            if len > MAX_FRAME_LEN {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("frame of {len} bytes exceeds the {MAX_FRAME_LEN} byte limit"),
+                ));
+            }
+            let mut frame = vec![0; len];
+            reader.read_exact(&mut frame).await?;
+            Ok(Some(frame))
+        }
+    }
+}
+
+pub(super) async fn write_frame<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    framing: Framing,
+    data: &[u8],
+) -> io::Result<()> {
+    if let Framing::LengthPrefixed = framing {
+        if data.len() > MAX_FRAME_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "frame of {} bytes exceeds the {MAX_FRAME_LEN} byte limit",
+                    data.len()
+                ),
+            ));
+        }
+        let mut len = data.len();
+        let mut prefix = Vec::with_capacity(5);
+        loop {
+            let byte = (len & 0x7f) as u8;
+            len >>= 7;
+            if len == 0 {
+                prefix.push(byte);
+                break;
+            }
+            prefix.push(byte | 0x80);
+        }
+        writer.write_all(&prefix).await?;
+    }
+    writer.write_all(data).await?;
+    writer.flush().await
+}
@@ -0,0 +1,584 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    future::Future,
+    io,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+    task::{Context, Poll},
+};
+
+use libp2p::{
+    core::{
+        muxing::{StreamMuxer, StreamMuxerEvent},
+        upgrade::{InboundConnectionUpgrade, OutboundConnectionUpgrade, UpgradeInfo},
+    },
+    futures::{AsyncRead, AsyncWrite},
+};
+
+/// multistream-select 的协商头，不代表具体协议
+const MULTISTREAM_HEADER: &[u8] = b"/multistream/1.0.0";
+/// 超过该长度的帧不再解析，只计字节
+const MAX_FRAME_LEN: usize = 1024 * 1024;
+/// 每个协议记住的消息 ID 数，用于识别重复副本
+const SEEN_CAPACITY: usize = 16 * 1024;
+
+/// pubsub 协议在统计中的名字，其余协议直接用协议名
+pub const GOSSIPSUB: &str = "gossipsub";
+pub const FLOODSUB: &str = "floodsub";
+
+/// 按协议名归类，gossipsub 的各个版本都算作一个协议
+fn protocol_label(protocol: &[u8]) -> String {
+    if protocol.starts_with(b"/meshsub/") {
+        GOSSIPSUB.to_string()
+    } else if protocol.starts_with(b"/floodsub/") {
+        FLOODSUB.to_string()
+    } else {
+        String::from_utf8_lossy(protocol).into_owned()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Direction {
+    Inbound,
+    Outbound,
+}
+
+/// 子流上收发的字节数，不含 yamux、noise 和 QUIC 自身的开销
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProtocolTraffic {
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+}
+
+/// 线上看到的某个话题的 pubsub 消息，包括协议内部去重前的副本
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TopicTraffic {
+    pub messages_in: u64,
+    /// 此前已收到或发出过的消息
+    pub duplicates_in: u64,
+    pub bytes_in: u64,
+    pub messages_out: u64,
+    pub bytes_out: u64,
+}
+
+/// 最近见过的消息 ID，超出容量时淘汰最早的
+#[derive(Default)]
+struct SeenCache {
+    ids: HashSet<Vec<u8>>,
+    order: VecDeque<Vec<u8>>,
+}
+
+impl SeenCache {
+    /// 返回 `false` 表示之前已经见过
+    fn insert(&mut self, id: Vec<u8>) -> bool {
+        if self.ids.contains(&id) {
+            return false;
+        }
+        if self.order.len() == SEEN_CAPACITY {
+            if let Some(oldest) = self.order.pop_front() {
+                self.ids.remove(&oldest);
+            }
+        }
+        self.ids.insert(id.clone());
+        self.order.push_back(id);
+        true
+    }
+}
+
+/// 单个子流的字节计数，读写时只做原子加法，不经过 [`Traffic`] 的锁
+#[derive(Default)]
+struct StreamCounters {
+    bytes_in: AtomicU64,
+    bytes_out: AtomicU64,
+}
+
+impl StreamCounters {
+    fn add(&self, direction: Direction, bytes: u64) {
+        let counter = match direction {
+            Direction::Inbound => &self.bytes_in,
+            Direction::Outbound => &self.bytes_out,
+        };
+        counter.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    fn load(&self) -> ProtocolTraffic {
+        ProtocolTraffic {
+            bytes_in: self.bytes_in.load(Ordering::Relaxed),
+            bytes_out: self.bytes_out.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[derive(Default)]
+struct TrafficStats {
+    /// 已关闭子流累计的字节
+    protocols: HashMap<String, ProtocolTraffic>,
+    /// 仍在使用的子流：子流编号 -> (协议, 计数)
+    streams: HashMap<u64, (String, Arc<StreamCounters>)>,
+    next_stream: u64,
+    topics: HashMap<(String, String), TopicTraffic>,
+    seen: HashMap<String, SeenCache>,
+}
+
+impl TrafficStats {
+    /// 协商完成的子流开始单独计数，协商阶段的字节算作初始值
+    fn open_stream(&mut self, protocol: &str, initial: ProtocolTraffic) -> StreamLabel {
+        let id = self.next_stream;
+        self.next_stream += 1;
+        let counters = Arc::new(StreamCounters {
+            bytes_in: AtomicU64::new(initial.bytes_in),
+            bytes_out: AtomicU64::new(initial.bytes_out),
+        });
+        self.streams
+            .insert(id, (protocol.to_string(), counters.clone()));
+        StreamLabel {
+            id,
+            protocol: protocol.to_string(),
+            counters,
+        }
+    }
+
+    /// 子流关闭后把计数并入协议累计值
+    fn close_stream(&mut self, id: u64) {
+        if let Some((protocol, counters)) = self.streams.remove(&id) {
+            let closed = counters.load();
+            let traffic = self.protocols.entry(protocol).or_default();
+            traffic.bytes_in += closed.bytes_in;
+            traffic.bytes_out += closed.bytes_out;
+        }
+    }
+
+    /// 记录一个 RPC 帧中发布的消息。gossipsub 与 floodsub 的消息都以 `from` 与 `seqno` 区分
+    fn add_rpc(&mut self, protocol: &str, direction: Direction, rpc: &[u8]) {
+        for (_, message) in fields(rpc).filter(|(field, _)| *field == RPC_PUBLISH) {
+            let mut id = Vec::new();
+            let mut topics = Vec::new();
+            for (field, value) in fields(message) {
+                match field {
+                    MESSAGE_FROM | MESSAGE_SEQNO => id.extend_from_slice(value),
+                    MESSAGE_TOPIC => topics.push(String::from_utf8_lossy(value).into_owned()),
+                    _ => {}
+                }
+            }
+            let first = self
+                .seen
+                .entry(protocol.to_string())
+                .or_default()
+                .insert(id);
+            let bytes = message.len() as u64;
+            for topic in topics {
+                let traffic = self
+                    .topics
+                    .entry((protocol.to_string(), topic))
+                    .or_default();
+                match direction {
+                    Direction::Inbound => {
+                        traffic.messages_in += 1;
+                        traffic.bytes_in += bytes;
+                        if !first {
+                            traffic.duplicates_in += 1;
+                        }
+                    }
+                    Direction::Outbound => {
+                        traffic.messages_out += 1;
+                        traffic.bytes_out += bytes;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// 节点按协议统计的流量，由各连接的子流共同写入
+#[derive(Clone, Default)]
+pub struct Traffic(Arc<Mutex<TrafficStats>>);
+
+impl Traffic {
+    fn lock(&self) -> MutexGuard<'_, TrafficStats> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 已关闭和仍在使用的子流之和
+    pub fn protocol(&self, protocol: &str) -> ProtocolTraffic {
+        let stats = self.lock();
+        let mut traffic = stats.protocols.get(protocol).copied().unwrap_or_default();
+        for (_, counters) in stats.streams.values().filter(|(p, _)| p == protocol) {
+            let live = counters.load();
+            traffic.bytes_in += live.bytes_in;
+            traffic.bytes_out += live.bytes_out;
+        }
+        traffic
+    }
+
+    pub fn topic(&self, protocol: &str, topic: &str) -> TopicTraffic {
+        self.lock()
+            .topics
+            .get(&(protocol.to_string(), topic.to_string()))
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+/// pubsub 的 protobuf 字段号，gossipsub 与 floodsub 相同
+const RPC_PUBLISH: u64 = 2;
+const MESSAGE_FROM: u64 = 1;
+const MESSAGE_SEQNO: u64 = 3;
+const MESSAGE_TOPIC: u64 = 4;
+
+fn read_varint(buf: &[u8]) -> Option<(u64, usize)> {
+    let mut value = 0u64;
+    for (i, byte) in buf.iter().enumerate().take(10) {
+        value |= u64::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Some((value, i + 1));
+        }
+    }
+    None
+}
+
+/// 遍历 protobuf 消息中长度前缀类型的字段，遇到无法解析的内容即停止
+fn fields(mut buf: &[u8]) -> impl Iterator<Item = (u64, &[u8])> {
+    std::iter::from_fn(move || loop {
+        let (key, n) = read_varint(buf)?;
+        buf = &buf[n..];
+        let len = match key & 0x07 {
+            0 => read_varint(buf)?.1,
+            1 => 8,
+            5 => 4,
+            2 => {
+                let (len, n) = read_varint(buf)?;
+                buf = &buf[n..];
+                let len = usize::try_from(len).ok().filter(|len| *len <= buf.len())?;
+                let (value, rest) = buf.split_at(len);
+                buf = rest;
+                return Some((key >> 3, value));
+            }
+            _ => return None,
+        };
+        buf = buf.get(len..)?;
+    })
+}
+
+/// 子流单个方向的字节流：先是 multistream-select 协商行，之后是长度前缀的帧
+#[derive(Default)]
+struct FrameReader {
+    buf: Vec<u8>,
+    /// 这个方向上提出或回应的协议
+    protocols: Vec<Vec<u8>>,
+    /// 协商确认前收到的数据帧（lazy 协商时拨号方会提前发送数据）
+    early: Vec<Vec<u8>>,
+    /// 非 pubsub 协议或无法解析时不再缓存数据
+    done: bool,
+}
+
+impl FrameReader {
+    fn next_frame(&mut self) -> Option<Vec<u8>> {
+        let (len, n) = read_varint(&self.buf)?;
+        let len = usize::try_from(len).unwrap_or(usize::MAX);
+        if len > MAX_FRAME_LEN {
+            self.stop();
+            return None;
+        }
+        if self.buf.len() < n + len {
+            return None;
+        }
+        let frame = self.buf[n..n + len].to_vec();
+        self.buf.drain(..n + len);
+        Some(frame)
+    }
+
+    fn stop(&mut self) {
+        self.done = true;
+        self.buf = Vec::new();
+        self.protocols = Vec::new();
+        self.early = Vec::new();
+    }
+}
+
+/// 协商完成的子流在 [`Traffic`] 中的登记
+struct StreamLabel {
+    id: u64,
+    protocol: String,
+    counters: Arc<StreamCounters>,
+}
+
+/// 记录流量的子流。协议由 multistream-select 协商识别：一方提出的协议被另一方原样回应后才算数，
+/// 回应 `na` 的提议不计入。协商完成后字节只计在本子流的原子计数上，
+/// pubsub 子流还会解析 RPC 帧，统计每个话题的消息副本
+pub struct MeteredStream<S> {
+    inner: S,
+    traffic: Traffic,
+    label: Option<StreamLabel>,
+    /// 协商完成之前的字节，之后计入该协议
+    pending: ProtocolTraffic,
+    inbound: FrameReader,
+    outbound: FrameReader,
+}
+
+impl<S> MeteredStream<S> {
+    fn new(inner: S, traffic: Traffic) -> Self {
+        Self {
+            inner,
+            traffic,
+            label: None,
+            pending: ProtocolTraffic::default(),
+            inbound: FrameReader::default(),
+            outbound: FrameReader::default(),
+        }
+    }
+
+    fn record(&mut self, direction: Direction, data: &[u8]) {
+        let bytes = data.len() as u64;
+        match &self.label {
+            Some(label) => label.counters.add(direction, bytes),
+            None => match direction {
+                Direction::Inbound => self.pending.bytes_in += bytes,
+                Direction::Outbound => self.pending.bytes_out += bytes,
+            },
+        }
+
+        let (reader, other) = match direction {
+            Direction::Inbound => (&mut self.inbound, &mut self.outbound),
+            Direction::Outbound => (&mut self.outbound, &mut self.inbound),
+        };
+        if reader.done {
+            return;
+        }
+        reader.buf.extend_from_slice(data);
+        while let Some(frame) = reader.next_frame() {
+            if self.label.is_none() {
+                // 协商行形如 `/ipfs/ping/1.0.0\n`，拒绝时回复 `na\n`
+                match frame.strip_suffix(b"\n") {
+                    Some(b"na") | Some(MULTISTREAM_HEADER) => {}
+                    Some(line) if line.starts_with(b"/") => {
+                        if other.protocols.iter().any(|p| p == line) {
+                            let protocol = protocol_label(line);
+                            let early = [
+                                (direction, std::mem::take(&mut reader.early)),
+                                (opposite(direction), std::mem::take(&mut other.early)),
+                            ];
+                            let mut stats = self.traffic.lock();
+                            let label = stats.open_stream(&protocol, self.pending);
+                            if matches!(protocol.as_str(), GOSSIPSUB | FLOODSUB) {
+                                for (direction, frames) in early {
+                                    for frame in frames {
+                                        stats.add_rpc(&protocol, direction, &frame);
+                                    }
+                                }
+                                reader.protocols = Vec::new();
+                                other.protocols = Vec::new();
+                            } else {
+                                reader.stop();
+                                other.stop();
+                            }
+                            self.label = Some(label);
+                            if reader.done {
+                                return;
+                            }
+                        } else {
+                            reader.protocols.push(line.to_vec());
+                        }
+                    }
+                    _ => {
+                        reader.early.push(frame);
+                        if reader.early.iter().map(Vec::len).sum::<usize>() > MAX_FRAME_LEN {
+                            reader.stop();
+                            other.stop();
+                            return;
+                        }
+                    }
+                }
+                continue;
+            }
+            match self.label.as_ref().map(|label| label.protocol.as_str()) {
+                Some(protocol @ (GOSSIPSUB | FLOODSUB)) => {
+                    self.traffic.lock().add_rpc(protocol, direction, &frame)
+                }
+                _ => {
+                    reader.stop();
+                    other.stop();
+                    return;
+                }
+            }
+        }
+    }
+}
+
+impl<S> Drop for MeteredStream<S> {
+    fn drop(&mut self) {
+        if let Some(label) = &self.label {
+            self.traffic.lock().close_stream(label.id);
+        }
+    }
+}
+
+fn opposite(direction: Direction) -> Direction {
+    match direction {
+        Direction::Inbound => Direction::Outbound,
+        Direction::Outbound => Direction::Inbound,
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for MeteredStream<S> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let n = std::task::ready!(Pin::new(&mut this.inner).poll_read(cx, buf))?;
+        this.record(Direction::Inbound, &buf[..n]);
+        Poll::Ready(Ok(n))
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for MeteredStream<S> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let n = std::task::ready!(Pin::new(&mut this.inner).poll_write(cx, buf))?;
+        this.record(Direction::Outbound, &buf[..n]);
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_close(cx)
+    }
+}
+
+/// 为连接上的每个子流包一层 [`MeteredStream`]
+pub struct MeteredMuxer<M> {
+    inner: M,
+    traffic: Traffic,
+}
+
+impl<M> MeteredMuxer<M> {
+    pub fn new(inner: M, traffic: Traffic) -> Self {
+        Self { inner, traffic }
+    }
+}
+
+impl<M> StreamMuxer for MeteredMuxer<M>
+where
+    M: StreamMuxer + Unpin,
+    M::Substream: Unpin,
+{
+    type Substream = MeteredStream<M::Substream>;
+    type Error = M::Error;
+
+    fn poll_inbound(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Self::Substream, Self::Error>> {
+        let this = self.get_mut();
+        Pin::new(&mut this.inner)
+            .poll_inbound(cx)
+            .map_ok(|stream| MeteredStream::new(stream, this.traffic.clone()))
+    }
+
+    fn poll_outbound(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Self::Substream, Self::Error>> {
+        let this = self.get_mut();
+        Pin::new(&mut this.inner)
+            .poll_outbound(cx)
+            .map_ok(|stream| MeteredStream::new(stream, this.traffic.clone()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.get_mut().inner).poll_close(cx)
+    }
+
+    fn poll(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<StreamMuxerEvent, Self::Error>> {
+        Pin::new(&mut self.get_mut().inner).poll(cx)
+    }
+}
+
+/// 包在 yamux 升级外层，让 TCP 与 WebSocket 连接也使用 [`MeteredMuxer`]
+#[derive(Clone)]
+pub struct MeteredUpgrade<U> {
+    inner: U,
+    traffic: Traffic,
+}
+
+impl<U> MeteredUpgrade<U> {
+    pub fn new(inner: U, traffic: Traffic) -> Self {
+        Self { inner, traffic }
+    }
+}
+
+impl<U: UpgradeInfo> UpgradeInfo for MeteredUpgrade<U> {
+    type Info = U::Info;
+    type InfoIter = U::InfoIter;
+
+    fn protocol_info(&self) -> Self::InfoIter {
+        self.inner.protocol_info()
+    }
+}
+
+impl<C, U> InboundConnectionUpgrade<C> for MeteredUpgrade<U>
+where
+    U: InboundConnectionUpgrade<C>,
+    U::Future: Unpin,
+{
+    type Output = MeteredMuxer<U::Output>;
+    type Error = U::Error;
+    type Future = Metering<U::Future>;
+
+    fn upgrade_inbound(self, socket: C, info: Self::Info) -> Self::Future {
+        Metering {
+            inner: self.inner.upgrade_inbound(socket, info),
+            traffic: self.traffic,
+        }
+    }
+}
+
+impl<C, U> OutboundConnectionUpgrade<C> for MeteredUpgrade<U>
+where
+    U: OutboundConnectionUpgrade<C>,
+    U::Future: Unpin,
+{
+    type Output = MeteredMuxer<U::Output>;
+    type Error = U::Error;
+    type Future = Metering<U::Future>;
+
+    fn upgrade_outbound(self, socket: C, info: Self::Info) -> Self::Future {
+        Metering {
+            inner: self.inner.upgrade_outbound(socket, info),
+            traffic: self.traffic,
+        }
+    }
+}
+
+pub struct Metering<F> {
+    inner: F,
+    traffic: Traffic,
+}
+
+impl<F, M, E> Future for Metering<F>
+where
+    F: Future<Output = Result<M, E>> + Unpin,
+{
+    type Output = Result<MeteredMuxer<M>, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        Pin::new(&mut this.inner)
+            .poll(cx)
+            .map_ok(|muxer| MeteredMuxer::new(muxer, this.traffic.clone()))
+    }
+}
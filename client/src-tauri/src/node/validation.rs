@@ -0,0 +1,520 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Result};
+use libp2p::{
+    gossipsub::{self, MessageAcceptance, TopicHash},
+    PeerId,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// 单个校验器的结论，对应 gossipsub 的 [`MessageAcceptance`]
+#[derive(Debug, Clone)]
+pub enum Verdict {
+    Accept,
+    /// 丢弃并扣转发者的分（P4 无效消息）
+    Reject(String),
+    /// 只丢弃不转发，不影响评分
+    Ignore(String),
+}
+
+impl Verdict {
+    pub fn acceptance(&self) -> MessageAcceptance {
+        match self {
+            Verdict::Accept => MessageAcceptance::Accept,
+            Verdict::Reject(_) => MessageAcceptance::Reject,
+            Verdict::Ignore(_) => MessageAcceptance::Ignore,
+        }
+    }
+
+    /// 拆成推送给前端的处理方式和原因，`Accept` 返回 `None`
+    pub fn rejection(self) -> Option<(Rejection, String)> {
+        match self {
+            Verdict::Accept => None,
+            Verdict::Reject(reason) => Some((Rejection::Reject, reason)),
+            Verdict::Ignore(reason) => Some((Rejection::Ignore, reason)),
+        }
+    }
+}
+
+/// 消息校验钩子，可以在 Rust 侧实现后通过 [`NodeManager::add_validator`](super::NodeManager::add_validator) 挂到话题上
+pub trait MessageValidator: Send {
+    fn name(&self) -> &'static str;
+
+    /// `propagation_source` 是把消息转发给我们的邻居，`message.source` 是原始发布者
+    fn validate(&mut self, propagation_source: &PeerId, message: &gossipsub::Message) -> Verdict;
+}
+
+/// 按顺序执行的校验器，遇到第一个非 Accept 的结论即停止
+#[derive(Default)]
+pub struct Pipeline {
+    validators: Vec<Box<dyn MessageValidator>>,
+}
+
+impl Pipeline {
+    pub fn push(&mut self, validator: Box<dyn MessageValidator>) {
+        self.validators.push(validator);
+    }
+
+    /// 全部通过时返回 `None`，否则返回拦下消息的校验器名和结论
+    pub fn validate(
+        &mut self,
+        propagation_source: &PeerId,
+        message: &gossipsub::Message,
+    ) -> Option<(&'static str, Verdict)> {
+        self.validators.iter_mut().find_map(|validator| {
+            match validator.validate(propagation_source, message) {
+                Verdict::Accept => None,
+                verdict => Some((validator.name(), verdict)),
+            }
+        })
+    }
+}
+
+/// 各话题的校验流水线，没有配置的话题全部接受
+#[derive(Default)]
+pub struct MessageValidation {
+    topics: HashMap<TopicHash, Pipeline>,
+}
+
+impl MessageValidation {
+    /// 替换话题的校验器，传空列表即取消校验
+    pub fn set(&mut self, topic: TopicHash, validators: Vec<ValidatorConfig>) -> Result<usize> {
+        let mut pipeline = Pipeline::default();
+        for config in validators {
+            pipeline.push(config.build()?);
+        }
+        let count = pipeline.validators.len();
+        if count == 0 {
+            self.topics.remove(&topic);
+        } else {
+            self.topics.insert(topic, pipeline);
+        }
+        Ok(count)
+    }
+
+    /// 在话题已有的校验器之后追加
+    pub fn push(&mut self, topic: TopicHash, validator: Box<dyn MessageValidator>) -> usize {
+        let pipeline = self.topics.entry(topic).or_default();
+        pipeline.push(validator);
+        pipeline.validators.len()
+    }
+
+    pub fn validate(
+        &mut self,
+        propagation_source: &PeerId,
+        message: &gossipsub::Message,
+    ) -> Option<(&'static str, Verdict)> {
+        self.topics
+            .get_mut(&message.topic)?
+            .validate(propagation_source, message)
+    }
+}
+
+/// 前端可配置的内置校验器
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum ValidatorConfig {
+    /// 消息体超过 `max_bytes` 时拒绝
+    #[serde(rename_all = "camelCase")]
+    MaxSize { max_bytes: usize },
+    /// 消息体必须是符合 schema 的 JSON，支持 JSON Schema 的常用子集
+    JsonSchema { schema: Value },
+    /// 只接受这些作者签名的消息
+    Signature { authors: Vec<PeerId> },
+    /// 每个作者在 `window_secs` 秒内最多 `max_messages` 条，超出的拒绝
+    #[serde(rename_all = "camelCase")]
+    RateLimit {
+        max_messages: usize,
+        window_secs: u64,
+    },
+}
+
+impl ValidatorConfig {
+    fn build(self) -> Result<Box<dyn MessageValidator>> {
+        Ok(match self {
+            ValidatorConfig::MaxSize { max_bytes } => Box::new(MaxSize(max_bytes)),
+            ValidatorConfig::JsonSchema { schema } => {
+                if !schema.is_object() {
+                    bail!("JSON schema must be an object");
+                }
+                Box::new(JsonSchema(schema))
+            }
+            ValidatorConfig::Signature { authors } => {
+                if authors.is_empty() {
+                    bail!("Signature policy needs at least one allowed author");
+                }
+                Box::new(SignaturePolicy(authors.into_iter().collect()))
+            }
+            ValidatorConfig::RateLimit {
+                max_messages,
+                window_secs,
+            } => {
+                if max_messages == 0 || window_secs == 0 {
+                    bail!("Rate limit needs a positive message count and window");
+                }
+                Box::new(RateLimit {
+                    max_messages,
+                    window: Duration::from_secs(window_secs),
+                    seen: HashMap::new(),
+                })
+            }
+        })
+    }
+}
+
+struct MaxSize(usize);
+
+impl MessageValidator for MaxSize {
+    fn name(&self) -> &'static str {
+        "maxSize"
+    }
+
+    fn validate(&mut self, _: &PeerId, message: &gossipsub::Message) -> Verdict {
+        if message.data.len() > self.0 {
+            Verdict::Reject(format!(
+                "message is {} bytes, limit is {}",
+                message.data.len(),
+                self.0
+            ))
+        } else {
+            Verdict::Accept
+        }
+    }
+}
+
+struct JsonSchema(Value);
+
+impl MessageValidator for JsonSchema {
+    fn name(&self) -> &'static str {
+        "jsonSchema"
+    }
+
+    fn validate(&mut self, _: &PeerId, message: &gossipsub::Message) -> Verdict {
+        let value: Value = match serde_json::from_slice(&message.data) {
+            Ok(value) => value,
+            Err(e) => return Verdict::Reject(format!("message is not JSON: {}", e)),
+        };
+        match check_schema(&self.0, &value, "$") {
+            Ok(()) => Verdict::Accept,
+            Err(reason) => Verdict::Reject(reason),
+        }
+    }
+}
+
+/// 支持 `type`、`enum`、`properties`、`required`、`additionalProperties`（布尔）、
+/// `items`、`minLength`/`maxLength` 与 `minimum`/`maximum`
+fn check_schema(schema: &Value, value: &Value, path: &str) -> Result<(), String> {
+    if let Some(expected) = schema.get("type") {
+        let types: Vec<&str> = match expected {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+            _ => vec![],
+        };
+        if !types.iter().any(|t| type_matches(t, value)) {
+            return Err(format!("{} should be {}", path, types.join(" or ")));
+        }
+    }
+    if let Some(Value::Array(allowed)) = schema.get("enum") {
+        if !allowed.contains(value) {
+            return Err(format!("{} is not one of the allowed values", path));
+        }
+    }
+
+    match value {
+        Value::Object(object) => {
+            if let Some(Value::Array(required)) = schema.get("required") {
+                for key in required.iter().filter_map(Value::as_str) {
+                    if !object.contains_key(key) {
+                        return Err(format!("{} is missing required property {}", path, key));
+                    }
+                }
+            }
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for (key, child) in object {
+                match properties.and_then(|p| p.get(key)) {
+                    Some(child_schema) => {
+                        check_schema(child_schema, child, &format!("{}.{}", path, key))?
+                    }
+                    None if schema.get("additionalProperties") == Some(&Value::Bool(false)) => {
+                        return Err(format!("{} has unexpected property {}", path, key));
+                    }
+                    None => {}
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    check_schema(item_schema, item, &format!("{}[{}]", path, i))?;
+                }
+            }
+        }
+        Value::String(s) => {
+            let len = s.chars().count() as u64;
+            if schema
+                .get("minLength")
+                .and_then(Value::as_u64)
+                .is_some_and(|min| len < min)
+            {
+                return Err(format!("{} is shorter than minLength", path));
+            }
+            if schema
+                .get("maxLength")
+                .and_then(Value::as_u64)
+                .is_some_and(|max| len > max)
+            {
+                return Err(format!("{} is longer than maxLength", path));
+            }
+        }
+        Value::Number(n) => {
+            let n = n.as_f64().unwrap_or_default();
+            if schema
+                .get("minimum")
+                .and_then(Value::as_f64)
+                .is_some_and(|min| n < min)
+            {
+                return Err(format!("{} is less than minimum", path));
+            }
+            if schema
+                .get("maximum")
+                .and_then(Value::as_f64)
+                .is_some_and(|max| n > max)
+            {
+                return Err(format!("{} is greater than maximum", path));
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn type_matches(expected: &str, value: &Value) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => false,
+    }
+}
+
+/// 签名本身由 gossipsub 的 Strict 模式校验，这里只检查作者是否在允许列表中
+struct SignaturePolicy(HashSet<PeerId>);
+
+impl MessageValidator for SignaturePolicy {
+    fn name(&self) -> &'static str {
+        "signature"
+    }
+
+    fn validate(&mut self, _: &PeerId, message: &gossipsub::Message) -> Verdict {
+        match &message.source {
+            None => Verdict::Reject("message is not signed".to_string()),
+            Some(author) if !self.0.contains(author) => {
+                Verdict::Reject(format!("author {} is not allowed", author))
+            }
+            Some(_) => Verdict::Accept,
+        }
+    }
+}
+
+/// 滑动窗口限速，按原始作者计数，匿名消息按转发者计数。
+///
+/// Reject 会通过 P4 扣转发者的分，所以只有作者本人发来的超限消息才 Reject，
+/// 诚实邻居转发的超限消息只 Ignore
+struct RateLimit {
+    max_messages: usize,
+    window: Duration,
+    seen: HashMap<PeerId, VecDeque<Instant>>,
+}
+
+impl MessageValidator for RateLimit {
+    fn name(&self) -> &'static str {
+        "rateLimit"
+    }
+
+    fn validate(&mut self, propagation_source: &PeerId, message: &gossipsub::Message) -> Verdict {
+        let peer = message.source.unwrap_or(*propagation_source);
+        if self.record(peer, Instant::now()) {
+            return Verdict::Accept;
+        }
+        let reason = format!(
+            "{} exceeded {} messages in {}s",
+            peer,
+            self.max_messages,
+            self.window.as_secs()
+        );
+        if peer == *propagation_source {
+            Verdict::Reject(reason)
+        } else {
+            Verdict::Ignore(reason)
+        }
+    }
+}
+
+impl RateLimit {
+    /// 在 `now` 记一条 `peer` 的消息，窗口内已满时不记录并返回 `false`
+    fn record(&mut self, peer: PeerId, now: Instant) -> bool {
+        // 顺带清理窗口外的记录，不再发消息的作者不会一直留在表里
+        self.seen.retain(|_, seen| {
+            while seen
+                .front()
+                .is_some_and(|at| now.duration_since(*at) > self.window)
+            {
+                seen.pop_front();
+            }
+            !seen.is_empty()
+        });
+
+        let seen = self.seen.entry(peer).or_default();
+        if seen.len() >= self.max_messages {
+            return false;
+        }
+        seen.push_back(now);
+        true
+    }
+}
+
+/// 未通过校验的消息处理方式
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Rejection {
+    Reject,
+    Ignore,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn message(source: Option<PeerId>, data: &[u8]) -> gossipsub::Message {
+        gossipsub::Message {
+            source,
+            data: data.to_vec(),
+            sequence_number: None,
+            topic: TopicHash::from_raw("test"),
+        }
+    }
+
+    fn rate_limit(max_messages: usize, window_secs: u64) -> RateLimit {
+        RateLimit {
+            max_messages,
+            window: Duration::from_secs(window_secs),
+            seen: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn schema_missing_required_field() {
+        let schema = json!({
+            "type": "object",
+            "required": ["name", "age"],
+        });
+        assert!(check_schema(&schema, &json!({ "name": "a", "age": 1 }), "$").is_ok());
+        assert_eq!(
+            check_schema(&schema, &json!({ "name": "a" }), "$").unwrap_err(),
+            "$ is missing required property age"
+        );
+    }
+
+    #[test]
+    fn schema_type_mismatch() {
+        let schema = json!({ "type": ["integer", "null"] });
+        assert!(check_schema(&schema, &json!(3), "$").is_ok());
+        assert!(check_schema(&schema, &Value::Null, "$").is_ok());
+        assert_eq!(
+            check_schema(&schema, &json!(1.5), "$").unwrap_err(),
+            "$ should be integer or null"
+        );
+        assert_eq!(
+            check_schema(&schema, &json!("3"), "$").unwrap_err(),
+            "$ should be integer or null"
+        );
+    }
+
+    #[test]
+    fn schema_nested() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "user": {
+                    "type": "object",
+                    "required": ["id"],
+                    "properties": { "id": { "type": "string", "minLength": 1 } },
+                    "additionalProperties": false,
+                },
+                "tags": { "type": "array", "items": { "type": "string" } },
+            },
+        });
+        let valid = json!({ "user": { "id": "x" }, "tags": ["a", "b"] });
+        assert!(check_schema(&schema, &valid, "$").is_ok());
+
+        let cases = [
+            (
+                json!({ "user": {} }),
+                "$.user is missing required property id",
+            ),
+            (
+                json!({ "user": { "id": "" } }),
+                "$.user.id is shorter than minLength",
+            ),
+            (
+                json!({ "user": { "id": "x", "admin": true } }),
+                "$.user has unexpected property admin",
+            ),
+            (json!({ "tags": ["a", 1] }), "$.tags[1] should be string"),
+        ];
+        for (value, error) in cases {
+            assert_eq!(check_schema(&schema, &value, "$").unwrap_err(), error);
+        }
+    }
+
+    #[test]
+    fn json_schema_rejects_non_json() {
+        let mut validator = JsonSchema(json!({ "type": "object" }));
+        let peer = PeerId::random();
+        let verdict = validator.validate(&peer, &message(Some(peer), b"not json"));
+        assert!(matches!(verdict, Verdict::Reject(_)));
+    }
+
+    #[test]
+    fn rate_limit_window_rollover() {
+        let mut limit = rate_limit(2, 10);
+        let peer = PeerId::random();
+        let start = Instant::now();
+        assert!(limit.record(peer, start));
+        assert!(limit.record(peer, start + Duration::from_secs(1)));
+        assert!(!limit.record(peer, start + Duration::from_secs(10)));
+        // 第一条滑出窗口后腾出一个名额，第二条仍在窗口内
+        assert!(limit.record(peer, start + Duration::from_secs(11)));
+        assert!(!limit.record(peer, start + Duration::from_secs(11)));
+        // 窗口内全部过期后记录被清理
+        assert!(limit.record(PeerId::random(), start + Duration::from_secs(30)));
+        assert!(!limit.seen.contains_key(&peer));
+    }
+
+    #[test]
+    fn rate_limit_ignores_relayed_messages() {
+        let mut limit = rate_limit(1, 60);
+        let (author, relay) = (PeerId::random(), PeerId::random());
+        let first = message(Some(author), b"1");
+        let second = message(Some(author), b"2");
+        assert!(matches!(limit.validate(&relay, &first), Verdict::Accept));
+        assert!(matches!(
+            limit.validate(&relay, &second),
+            Verdict::Ignore(_)
+        ));
+        assert!(matches!(
+            limit.validate(&author, &second),
+            Verdict::Reject(_)
+        ));
+    }
+}
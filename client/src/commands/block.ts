@@ -0,0 +1,52 @@
+import { invoke } from "@tauri-apps/api/core";
+
+/** 本地块存储中的一个块 */
+export interface BlockInfo {
+  cid: string;
+  codec: "raw" | "dag-json";
+  size: number;
+  /** DAG 节点的链接数，叶子为 0 */
+  links: number;
+}
+
+export interface AddedDag {
+  /** 根节点的 CID */
+  cid: string;
+  name: string;
+  size: number;
+  /** DAG 中的块数，包括叶子和中间节点 */
+  blocks: number;
+}
+
+export interface FetchedDag {
+  cid: string;
+  /** 根节点是文件 DAG 时的文件名 */
+  name: string | null;
+  size: number;
+  blocks: number;
+  /** 本次从网络取回的块数，其余已在本地 */
+  fetched: number;
+  /** 指定了保存目录时写出的文件路径 */
+  path: string | null;
+}
+
+/** 块交换事件 */
+export type BlockEvent =
+  | { kind: "sent"; peerId: string; cid: string; size: number }
+  | { kind: "received"; peerId: string; cid: string; size: number }
+  /** 对方发来的数据与 CID 不符，已丢弃 */
+  | { kind: "invalid"; peerId: string; cid: string };
+
+/** 把文件切块组成 Merkle DAG，并在 DHT 上发布根 CID 的 provider 记录 */
+export function addDagFile(path: string, nodeId?: string) {
+  return invoke<AddedDag>("add_dag_file", { nodeId, path });
+}
+
+/** 先问已连接的节点，都没有时通过 DHT 查找 provider；指定 destDir 时还原为文件 */
+export function fetchDag(cid: string, destDir?: string, nodeId?: string) {
+  return invoke<FetchedDag>("fetch_dag", { nodeId, cid, destDir });
+}
+
+export function listBlocks(nodeId?: string) {
+  return invoke<BlockInfo[]>("list_blocks", { nodeId });
+}
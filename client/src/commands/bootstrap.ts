@@ -0,0 +1,20 @@
+import { invoke } from "@tauri-apps/api/core";
+
+/** 保存的引导节点列表，节点启动时拨号 */
+export function getBootstrapPeers() {
+  return invoke<string[]>("get_bootstrap_peers");
+}
+
+/** 添加引导节点，地址必须以 /p2p/<PeerId> 结尾 */
+export function addBootstrapPeer(addr: string) {
+  return invoke<string[]>("add_bootstrap_peer", { addr });
+}
+
+export function removeBootstrapPeer(addr: string) {
+  return invoke<string[]>("remove_bootstrap_peer", { addr });
+}
+
+/** 整体替换引导列表，任一地址无效时保持原列表 */
+export function setBootstrapPeers(addrs: string[]) {
+  return invoke<string[]>("set_bootstrap_peers", { addrs });
+}
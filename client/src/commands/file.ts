@@ -0,0 +1,83 @@
+import { invoke, Channel } from "@tauri-apps/api/core";
+
+export type HashAlgorithm = "sha256" | "blake3";
+
+/** 对外提供的文件，fileId 即文件内容的哈希 */
+export interface FileOffer {
+  fileId: string;
+  name: string;
+  size: number;
+  algorithm: HashAlgorithm;
+}
+
+export interface DownloadedFile {
+  path: string;
+  offer: FileOffer;
+  /** 续传时本次开始的位置 */
+  resumedFrom: number;
+}
+
+/** 下载进度，取消下载需要 started 中的 transferId */
+export type TransferEvent =
+  /** offset 大于 0 表示从上次中断处续传 */
+  | { type: "started"; transferId: number; offer: FileOffer; offset: number }
+  | {
+      type: "progress";
+      transferId: number;
+      received: number;
+      total: number;
+      bytesPerSec: number;
+    }
+  /** 接收完毕，正在计算哈希 */
+  | { type: "verifying"; transferId: number }
+  | { type: "completed"; transferId: number; path: string; elapsedMs: number }
+  /** 已接收的部分保留在 .part 文件中，再次下载时续传 */
+  | { type: "failed"; transferId: number; error: string };
+
+/** 向其他节点发送文件的情况 */
+export type FileEvent =
+  | { kind: "serving"; peerId: string; fileId: string; offset: number }
+  | { kind: "served"; peerId: string; fileId: string; bytes: number }
+  | { kind: "serveFailed"; peerId: string; fileId: string | null; error: string };
+
+/** 计算哈希后对外提供本地文件，缺省使用 SHA-256 */
+export function offerFile(path: string, algorithm?: HashAlgorithm, nodeId?: string) {
+  return invoke<FileOffer>("offer_file", { nodeId, path, algorithm });
+}
+
+export function removeOffer(fileId: string, nodeId?: string) {
+  return invoke("remove_offer", { nodeId, fileId });
+}
+
+export function listOffers(nodeId?: string) {
+  return invoke<FileOffer[]>("list_offers", { nodeId });
+}
+
+/** 查询对方提供的文件 */
+export function listRemoteFiles(peerId: string, nodeId?: string) {
+  return invoke<FileOffer[]>("list_remote_files", { nodeId, peerId });
+}
+
+/** 下载文件到 destDir，之前中断的下载会从已接收的位置续传 */
+export function downloadFile(
+  peerId: string,
+  fileId: string,
+  destDir: string,
+  onProgress: (event: TransferEvent) => void,
+  nodeId?: string
+) {
+  const channel = new Channel<TransferEvent>();
+  channel.onmessage = onProgress;
+  return invoke<DownloadedFile>("download_file", {
+    nodeId,
+    peerId,
+    fileId,
+    destDir,
+    onProgress: channel,
+  });
+}
+
+/** 中止下载，已接收的部分留待续传 */
+export function cancelTransfer(transferId: number, nodeId?: string) {
+  return invoke("cancel_transfer", { nodeId, transferId });
+}
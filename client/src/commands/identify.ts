@@ -1,9 +1,16 @@
 import { invoke, Channel } from "@tauri-apps/api/core";
+import type {
+  CommandReply,
+  ConnectionEvent,
+  DiscoveredPeer,
+  NodeConfig,
+} from "./node";
 
 export type IdentifyEvent =
   | { type: "listening"; addr: string }
-  | { type: "connected"; peerId: string }
-  | { type: "disconnected"; peerId: string }
+  | ConnectionEvent
+  | ({ type: "discovered" } & DiscoveredPeer)
+  | ({ type: "expired" } & DiscoveredPeer)
   | { type: "ping"; peerId: string; rttMs: number }
   | {
       type: "identified";
@@ -19,12 +26,16 @@ export type IdentifyCommand =
   | { command: "disconnect"; addr: string }
   | { command: "stop" };
 
-export function startIdentifyNode(onEvent: (event: IdentifyEvent) => void) {
+export function startIdentifyNode(
+  onEvent: (event: IdentifyEvent) => void,
+  config?: NodeConfig
+) {
   const channel = new Channel<IdentifyEvent>();
   channel.onmessage = onEvent;
-  return invoke<string>("start_identify_node", { onEvent: channel });
+  return invoke<string>("start_identify_node", { config, onEvent: channel });
 }
 
 export function sendIdentifyCommand(cmd: IdentifyCommand) {
-  return invoke("send_identify_command", { cmd });
+  // stop 没有结果，返回 null
+  return invoke<CommandReply | null>("send_identify_command", { cmd });
 }
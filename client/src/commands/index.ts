@@ -2,3 +2,15 @@ export * from "./peer-id";
 export * from "./ping";
 export * from "./multiaddr";
 export * from "./identify";
+export * from "./node";
+export * from "./keystore";
+export * from "./vanity";
+export * from "./kad";
+export * from "./rendezvous";
+export * from "./bootstrap";
+export * from "./pubsub";
+export * from "./rpc";
+export * from "./stream";
+export * from "./file";
+export * from "./block";
+export * from "./relay";
@@ -0,0 +1,64 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export interface KadPeer {
+  peerId: string;
+  addrs: string[];
+}
+
+/** DHT 查询的单步结果 */
+export type KadQueryResult =
+  | { kind: "bootstrap"; peerId: string; numRemaining: number }
+  | { kind: "closestPeers"; key: string; peers: KadPeer[] }
+  | {
+      kind: "foundRecord";
+      key: string;
+      value: string;
+      publisher: string | null;
+      /** 为 null 表示来自本地存储 */
+      peerId: string | null;
+    }
+  | { kind: "putRecord"; key: string }
+  | { kind: "startProviding"; key: string }
+  | { kind: "foundProviders"; key: string; providers: string[] }
+  | { kind: "finished" }
+  | { kind: "failed"; error: string };
+
+export type KadEvent =
+  | {
+      type: "kadQueryProgressed";
+      queryId: string;
+      step: number;
+      last: boolean;
+      result: KadQueryResult;
+    }
+  | {
+      type: "kadRoutingUpdated";
+      peerId: string;
+      isNewPeer: boolean;
+      addresses: string[];
+      bucket: number | null;
+      evicted: string | null;
+    }
+  | { type: "kadModeChanged"; mode: "client" | "server" };
+
+export type KadCommand =
+  | { command: "kadBootstrap" }
+  | { command: "kadGetClosestPeers"; peerId: string }
+  | { command: "kadPutRecord"; key: string; value: string }
+  | { command: "kadGetRecord"; key: string }
+  | { command: "kadStartProviding"; key: string }
+  | { command: "kadGetProviders"; key: string };
+
+export interface KBucketEntry {
+  bucket: number;
+  peerId: string;
+  /** 与本地节点的 XOR 距离（十六进制） */
+  distance: string;
+  addresses: string[];
+  connected: boolean;
+  lastSeenMs: number | null;
+}
+
+export function getRoutingTable(nodeId?: string) {
+  return invoke<KBucketEntry[]>("get_routing_table", { nodeId });
+}
@@ -0,0 +1,46 @@
+import { invoke } from "@tauri-apps/api/core";
+import type { KeyFormat, KeyType } from "./peer-id";
+
+/** 本地身份概要 */
+export interface IdentityInfo {
+  peerId: string;
+  keyType: KeyType;
+  /** 是否使用口令加密保存 */
+  encrypted: boolean;
+  /** 私钥是否已加载到内存 */
+  unlocked: boolean;
+}
+
+/** 查看本地身份，尚未生成时返回 null */
+export function getIdentity() {
+  return invoke<IdentityInfo | null>("get_identity");
+}
+
+/** 启动时身份文件损坏的原因，坏文件已被移到一旁并会生成新身份 */
+export function getIdentityError() {
+  return invoke<string | null>("get_identity_error");
+}
+
+/** 生成新的本地身份，节点重启后生效 */
+export function createIdentity(keyType: KeyType, passphrase?: string) {
+  return invoke<IdentityInfo>("create_identity", { keyType, passphrase });
+}
+
+/** 用口令解锁加密的身份 */
+export function unlockIdentity(passphrase: string) {
+  return invoke<IdentityInfo>("unlock_identity", { passphrase });
+}
+
+/** 修改身份口令，传空则改为明文保存 */
+export function setIdentityPassphrase(passphrase?: string) {
+  return invoke<IdentityInfo>("set_identity_passphrase", { passphrase });
+}
+
+/** 导入外部密钥作为节点身份，节点重启后生效 */
+export function importIdentity(
+  data: string,
+  format: KeyFormat,
+  passphrase?: string
+) {
+  return invoke<IdentityInfo>("import_identity", { data, format, passphrase });
+}
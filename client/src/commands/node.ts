@@ -0,0 +1,194 @@
+import { invoke, Channel } from "@tauri-apps/api/core";
+import type { KadCommand, KadEvent } from "./kad";
+import type {
+  ComparisonReport,
+  FloodsubCommand,
+  FloodsubEvent,
+  GossipsubCommand,
+  GossipsubEvent,
+  PubsubCompareCommand,
+  ScoreConfig,
+} from "./pubsub";
+import type { RendezvousCommand, RendezvousEvent } from "./rendezvous";
+import type { Responder, RpcCommand, RpcConfig, RpcEvent } from "./rpc";
+import type { StreamEvent } from "./stream";
+import type { FileEvent } from "./file";
+import type { BlockEvent } from "./block";
+import type { RelayEvent, RelayServerConfig } from "./relay";
+
+export type NodeState = "starting" | "running" | "stopping" | "stopped";
+
+export interface ConnectionInfo {
+  peerId: string;
+  connectionId: string;
+  /** tcp、quic-v1、ws、wss */
+  transport: string;
+  role: "dialer" | "listener";
+  remoteAddr: string;
+  numEstablished: number;
+  establishedInMs: number;
+}
+
+export interface ClosedConnection {
+  peerId: string;
+  connectionId: string;
+  remoteAddr: string;
+  numEstablished: number;
+  /** 为 null 表示本地主动关闭 */
+  cause: string | null;
+}
+
+export interface DialFailure {
+  peerId: string | null;
+  connectionId: string;
+  error: string;
+}
+
+/** mDNS 发现或过期的节点 */
+export interface DiscoveredPeer {
+  peerId: string;
+  addrs: string[];
+}
+
+export type ConnectionEvent =
+  | { type: "dialing"; peerId: string | null; connectionId: string }
+  | ({ type: "dialFailed" } & DialFailure)
+  | ({ type: "connected" } & ConnectionInfo)
+  | ({ type: "disconnected" } & ClosedConnection);
+
+export type NodeEvent =
+  | { type: "stateChanged"; state: NodeState }
+  | { type: "listening"; addr: string }
+  | { type: "expiredListenAddr"; addr: string }
+  | { type: "listenerClosed"; addrs: string[]; reason: string | null }
+  | { type: "listenerError"; message: string }
+  | ConnectionEvent
+  | {
+      type: "incomingConnectionFailed";
+      peerId: string | null;
+      connectionId: string;
+      sendBackAddr: string;
+      error: string;
+    }
+  | { type: "ping"; peerId: string; rttMs: number }
+  | {
+      type: "identified";
+      peerId: string;
+      protocolVersion: string;
+      agentVersion: string;
+      listenAddrs: string[];
+    }
+  | ({ type: "mdnsDiscovered" } & DiscoveredPeer)
+  | ({ type: "mdnsExpired" } & DiscoveredPeer)
+  | { type: "bootstrapDialing"; peerId: string; attempt: number }
+  | { type: "bootstrapBackoff"; peerId: string; attempt: number; retryInMs: number }
+  | KadEvent
+  | ({ type: "rendezvous" } & RendezvousEvent)
+  | ({ type: "gossipsub" } & GossipsubEvent)
+  | ({ type: "floodsub" } & FloodsubEvent)
+  /** 对比消息已全部发出，附本节点此时的统计 */
+  | ({ type: "comparisonFinished" } & ComparisonReport)
+  | ({ type: "rpc" } & RpcEvent)
+  | ({ type: "stream" } & StreamEvent)
+  /** 向其他节点发送文件的情况，下载进度走 downloadFile 的回调 */
+  | ({ type: "file" } & FileEvent)
+  | ({ type: "block" } & BlockEvent)
+  | ({ type: "relay" } & RelayEvent)
+  | { type: "error"; message: string };
+
+export type NodeCommand =
+  | { command: "dial"; addr: string }
+  | { command: "dialPeer"; peerId: string; addrs: string[] }
+  | { command: "disconnect"; peerId: string }
+  | { command: "listenOn"; addr: string }
+  | { command: "removeListener"; addr: string }
+  | { command: "setMdnsAutoDial"; enabled: boolean }
+  | KadCommand
+  | RendezvousCommand
+  | GossipsubCommand
+  | FloodsubCommand
+  | PubsubCompareCommand
+  | RpcCommand;
+
+/** 命令执行结果，失败时 invoke 会 reject 并带上错误信息 */
+export type CommandReply =
+  | { type: "dialing"; connectionId: string }
+  | { type: "disconnected"; peerId: string }
+  | { type: "listening"; listenerId: string }
+  | { type: "listenerRemoved"; listenerId: string }
+  | { type: "mdnsAutoDial"; enabled: boolean }
+  | { type: "query"; queryId: string }
+  | { type: "rendezvousRequested"; rendezvousNode: string }
+  /** changed 为 false 表示此前已经订阅 */
+  | { type: "subscribed"; topic: string; changed: boolean }
+  | { type: "unsubscribed"; topic: string }
+  | { type: "published"; messageId: string }
+  | { type: "validatorsSet"; topic: string; count: number }
+  | { type: "floodsubPublished"; topic: string }
+  | { type: "compareStarted"; topic: string }
+  | { type: "comparePublishing"; topic: string; count: number }
+  | { type: "compareStopped"; topic: string }
+  | { type: "rpcResponderSet"; responder: Responder };
+
+/** 节点启动参数，节点已在运行时会被忽略 */
+export interface NodeConfig {
+  /** 监听地址，如 /ip4/0.0.0.0/tcp/0、/ip6/::/udp/0/quic-v1 */
+  listenAddrs?: string[];
+  /** 启动后自动拨号同进程内的其他节点 */
+  autoDial?: boolean;
+  /** 通过 mDNS 发现局域网节点，默认开启 */
+  mdns?: boolean;
+  /** 自动拨号 mDNS 发现的节点 */
+  mdnsAutoDial?: boolean;
+  /** 作为 rendezvous 服务端接受其他节点注册 */
+  rendezvousServer?: boolean;
+  /** 启动时拨号的引导节点，缺省使用保存的引导列表 */
+  bootstrapPeers?: string[];
+  /** gossipsub 节点评分参数 */
+  gossipsubScore?: ScoreConfig;
+  /** 请求-响应协议名、编码与自动应答方式 */
+  rpc?: RpcConfig;
+  /** 作为中继服务端为其他节点保留预约并转发连接，不传时不提供中继 */
+  relayServer?: RelayServerConfig;
+}
+
+export interface NodeInfo {
+  nodeId: string;
+  peerId: string;
+  state: NodeState;
+}
+
+/** 主节点 ID，使用密钥库身份 */
+export const DEFAULT_NODE = "default";
+
+/**
+ * 启动（或挂载到）节点；`nodeId` 缺省为主节点，
+ * 其他 ID 会以临时身份在进程内再启动一个节点
+ */
+export function startNode(
+  onEvent: (event: NodeEvent) => void,
+  config?: NodeConfig,
+  nodeId?: string
+) {
+  const channel = new Channel<NodeEvent>();
+  channel.onmessage = onEvent;
+  return invoke<string>("start_node", { nodeId, config, onEvent: channel });
+}
+
+export function sendNodeCommand(cmd: NodeCommand, nodeId?: string) {
+  return invoke<CommandReply>("send_node_command", { nodeId, cmd });
+}
+
+/** 关闭监听器和连接后停止节点，resolve 时节点已完全退出 */
+export function stopNode(nodeId?: string) {
+  return invoke("stop_node", { nodeId });
+}
+
+export function listNodes() {
+  return invoke<NodeInfo[]>("list_nodes");
+}
+
+/** 让 `nodeId` 拨号同进程内的 `target` 节点 */
+export function dialLocalNode(nodeId: string, target: string) {
+  return invoke<CommandReply>("dial_local_node", { nodeId, target });
+}
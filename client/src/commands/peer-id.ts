@@ -8,6 +8,94 @@ export interface PeerIdResult {
 
 export type KeyType = "ed25519" | "secp256k1" | "ecdsa";
 
+/** 密钥导入导出格式：protobuf 十六进制或 PKCS#8 PEM */
+export type KeyFormat = "protobuf" | "pem";
+
+export interface ExportedKeypair {
+  peer_id: string;
+  key_type: string;
+  format: KeyFormat;
+  data: string;
+}
+
+/** PeerId 解析结果 */
+export interface PeerIdInfo {
+  peer_id: string;
+  /** multihash 算法名 */
+  multihash: string;
+  multihash_code: number;
+  digest_hex: string;
+  /** 只有 identity 哈希的 PeerId 才内嵌公钥 */
+  key_type: string | null;
+  public_key_hex: string | null;
+}
+
+/** 生成临时 PeerId，只返回公钥 */
 export function generatePeerId(keyType: KeyType) {
   return invoke<PeerIdResult>("generate_peer_id", { keyType });
 }
+
+/** 生成密钥并导出（包含私钥），protobuf 格式的 data 可作为 keypairHex 使用 */
+export function generateKeypair(
+  keyType: KeyType,
+  format: KeyFormat = "protobuf"
+) {
+  return invoke<ExportedKeypair>("generate_keypair", { keyType, format });
+}
+
+/** 导出密钥；不传 keypairHex 时导出节点当前身份 */
+export function exportKeypair(format: KeyFormat, keypairHex?: string) {
+  return invoke<ExportedKeypair>("export_keypair", { keypairHex, format });
+}
+
+/** 解析导入的密钥，不会改动节点身份 */
+export function importKeypair(data: string, format: KeyFormat) {
+  return invoke<PeerIdResult>("import_keypair", { data, format });
+}
+
+export function inspectPeerId(peerId: string) {
+  return invoke<PeerIdInfo>("inspect_peer_id", { peerId });
+}
+
+/** 待签名消息的编码方式 */
+export type MessageEncoding = "text" | "hex" | "base64";
+
+export interface SignatureResult {
+  peer_id: string;
+  public_key_hex: string;
+  signature_hex: string;
+}
+
+export interface VerifyResult {
+  valid: boolean;
+  peer_id: string;
+  key_type: string;
+}
+
+/** 签名消息；不传 keypairHex 时使用节点当前身份 */
+export function signMessage(
+  message: string,
+  encoding: MessageEncoding = "text",
+  keypairHex?: string
+) {
+  return invoke<SignatureResult>("sign_message", {
+    message,
+    encoding,
+    keypairHex,
+  });
+}
+
+/** 用 PeerId 或编码后的公钥（hex）校验签名 */
+export function verifySignature(
+  message: string,
+  signatureHex: string,
+  signer: string,
+  encoding: MessageEncoding = "text"
+) {
+  return invoke<VerifyResult>("verify_signature", {
+    message,
+    encoding,
+    signatureHex,
+    signer,
+  });
+}
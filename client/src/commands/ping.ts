@@ -1,9 +1,16 @@
 import { invoke, Channel } from "@tauri-apps/api/core";
+import type {
+  CommandReply,
+  ConnectionEvent,
+  DiscoveredPeer,
+  NodeConfig,
+} from "./node";
 
 export type PingEvent =
   | { type: "listening"; addr: string }
-  | { type: "connected"; peerId: string }
-  | { type: "disconnected"; peerId: string }
+  | ConnectionEvent
+  | ({ type: "discovered" } & DiscoveredPeer)
+  | ({ type: "expired" } & DiscoveredPeer)
   | { type: "ping"; peerId: string; rttMs: number }
   | { type: "error"; message: string };
 
@@ -12,12 +19,16 @@ export type PingCommand =
   | { command: "disconnect"; addr: string }
   | { command: "stop" };
 
-export function startPingNode(onEvent: (event: PingEvent) => void) {
+export function startPingNode(
+  onEvent: (event: PingEvent) => void,
+  config?: NodeConfig
+) {
   const channel = new Channel<PingEvent>();
   channel.onmessage = onEvent;
-  return invoke<string>("start_ping_node", { onEvent: channel });
+  return invoke<string>("start_ping_node", { config, onEvent: channel });
 }
 
 export function sendPingCommand(cmd: PingCommand) {
-  return invoke("send_ping_command", { cmd });
+  // stop 没有结果，返回 null
+  return invoke<CommandReply | null>("send_ping_command", { cmd });
 }
@@ -0,0 +1,202 @@
+import { invoke } from "@tauri-apps/api/core";
+
+/** gossipsub 事件 */
+export type GossipsubEvent =
+  | {
+      kind: "message";
+      topic: string;
+      /** 原始发布者 */
+      source: string | null;
+      /** 转发给我们的邻居 */
+      propagationSource: string;
+      /** 超出 JS 安全整数范围，以字符串传递 */
+      sequenceNumber: string | null;
+      messageId: string;
+      data: string;
+    }
+  /** 未通过校验的消息，validator 是拦下它的校验器 */
+  | {
+      kind: "invalid";
+      topic: string;
+      source: string | null;
+      propagationSource: string;
+      messageId: string;
+      /** reject 会扣转发者的分，ignore 只丢弃 */
+      acceptance: "reject" | "ignore";
+      validator: string;
+      reason: string;
+    }
+  | { kind: "subscribed"; peerId: string; topic: string }
+  | { kind: "unsubscribed"; peerId: string; topic: string }
+  | { kind: "notSupported"; peerId: string }
+  | { kind: "slowPeer"; peerId: string; failed: number }
+  /** 按 scoreConfig.reportIntervalSecs 定时推送 */
+  | { kind: "scores"; peers: PeerScore[] };
+
+/** 节点评分参数，启动节点时传入，缺省值适合消息很少的实验网络 */
+export interface ScoreConfig {
+  gossipThreshold?: number;
+  publishThreshold?: number;
+  /** 低于该分数忽略对方全部 RPC */
+  graylistThreshold?: number;
+  acceptPxThreshold?: number;
+  opportunisticGraftThreshold?: number;
+  topicScoreCap?: number;
+  /** P6：同一 IP 上的节点数超过阈值后按超出数的平方惩罚 */
+  ipColocationFactorWeight?: number;
+  ipColocationFactorThreshold?: number;
+  /** P7：协议违规惩罚 */
+  behaviourPenaltyWeight?: number;
+  behaviourPenaltyDecay?: number;
+  /** 不小于 1000 */
+  decayIntervalMs?: number;
+  /** 0 表示不推送 */
+  reportIntervalSecs?: number;
+  topic?: TopicScoreConfig;
+}
+
+export interface TopicScoreConfig {
+  topicWeight?: number;
+  /** P1：在 mesh 中的时间 */
+  timeInMeshWeight?: number;
+  timeInMeshQuantumMs?: number;
+  timeInMeshCap?: number;
+  /** P2：第一个送达的消息数 */
+  firstMessageDeliveriesWeight?: number;
+  firstMessageDeliveriesDecay?: number;
+  firstMessageDeliveriesCap?: number;
+  /** P3：mesh 内投递不足，0 表示关闭 */
+  meshMessageDeliveriesWeight?: number;
+  meshMessageDeliveriesThreshold?: number;
+  /** P4：未通过校验的消息数 */
+  invalidMessageDeliveriesWeight?: number;
+  invalidMessageDeliveriesDecay?: number;
+}
+
+export interface PeerScore {
+  peerId: string;
+  /** libp2p 计算的总分 */
+  score: number;
+  topics: TopicScore[];
+  /** P6 IP 共置惩罚 */
+  ipColocation: number;
+  /** 未单独列出的部分：P3、P7、话题分上限等 */
+  other: number;
+}
+
+/** 各 `*Score` 已乘上话题权重 */
+export interface TopicScore {
+  topic: string;
+  inMesh: boolean;
+  meshTimeMs: number;
+  timeInMeshScore: number;
+  firstMessageDeliveries: number;
+  firstMessageDeliveriesScore: number;
+  invalidMessageDeliveries: number;
+  invalidMessageDeliveriesScore: number;
+}
+
+/** 内置的消息校验器，按顺序执行 */
+export type ValidatorConfig =
+  | { kind: "maxSize"; maxBytes: number }
+  /** 支持 JSON Schema 的常用子集：type、enum、properties、required 等 */
+  | { kind: "jsonSchema"; schema: Record<string, unknown> }
+  /** 只接受这些作者签名的消息 */
+  | { kind: "signature"; authors: string[] }
+  /** 每个作者在 windowSecs 秒内最多 maxMessages 条 */
+  | { kind: "rateLimit"; maxMessages: number; windowSecs: number };
+
+export type GossipsubCommand =
+  | { command: "gossipsubSubscribe"; topic: string }
+  | { command: "gossipsubUnsubscribe"; topic: string }
+  /** 需要至少一个已连接的节点订阅了该话题 */
+  | { command: "gossipsubPublish"; topic: string; data: string }
+  /** 替换话题的校验器，传空列表即取消校验 */
+  | { command: "setMessageValidators"; topic: string; validators: ValidatorConfig[] };
+
+export interface TopicMesh {
+  topic: string;
+  /** 当前 mesh 中直接转发完整消息的节点 */
+  meshPeers: string[];
+  /** 所有已知订阅了该话题的节点 */
+  subscribers: string[];
+}
+
+/** 已订阅话题及其 mesh 节点 */
+export function getMeshPeers(nodeId?: string) {
+  return invoke<TopicMesh[]>("get_mesh_peers", { nodeId });
+}
+
+/** 各节点的评分明细，按总分从高到低排列 */
+export function getPeerScores(nodeId?: string) {
+  return invoke<PeerScore[]>("get_peer_scores", { nodeId });
+}
+
+/** floodsub 事件，floodsub 不区分转发者 */
+export type FloodsubEvent =
+  | {
+      kind: "message";
+      topics: string[];
+      source: string;
+      sequenceNumber: string;
+      data: string;
+    }
+  | { kind: "subscribed"; peerId: string; topic: string }
+  | { kind: "unsubscribed"; peerId: string; topic: string };
+
+export type FloodsubCommand =
+  | { command: "floodsubSubscribe"; topic: string }
+  | { command: "floodsubUnsubscribe"; topic: string }
+  /** 没有已连接且订阅了该话题的节点时消息直接丢弃 */
+  | { command: "floodsubPublish"; topic: string; data: string };
+
+/** 同一话题同时走 gossipsub 与 floodsub，参与的节点都要先 start */
+export type PubsubCompareCommand =
+  | { command: "pubsubCompareStart"; topic: string }
+  | {
+      command: "pubsubComparePublish";
+      topic: string;
+      count: number;
+      /** 每条消息的大致字节数 */
+      size: number;
+      intervalMs: number;
+    }
+  | { command: "pubsubCompareStop"; topic: string };
+
+export interface LatencyStats {
+  min: number;
+  avg: number;
+  p50: number;
+  p95: number;
+  max: number;
+}
+
+/** 均从本节点视角统计 */
+export interface ProtocolReport {
+  protocol: "gossipsub" | "floodsub";
+  sent: number;
+  failed: number;
+  received: number;
+  /** 从发布到收到的毫秒数，跨机器时依赖时钟同步 */
+  latencyMs: LatencyStats | null;
+  /** 线上收到的该话题消息副本数，duplicates 为协议去重时丢弃的 */
+  copiesIn: number;
+  duplicates: number;
+  messageBytesIn: number;
+  messageBytesOut: number;
+  /** 该协议所有子流的字节数，含控制消息 */
+  bytesIn: number;
+  bytesOut: number;
+}
+
+export interface ComparisonReport {
+  topic: string;
+  elapsedMs: number;
+  publishing: boolean;
+  protocols: ProtocolReport[];
+}
+
+/** 各话题 gossipsub 与 floodsub 的对比结果 */
+export function getPubsubComparison(nodeId?: string) {
+  return invoke<ComparisonReport[]>("get_pubsub_comparison", { nodeId });
+}
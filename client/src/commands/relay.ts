@@ -0,0 +1,48 @@
+/** 中继服务端的预约与中继连接上限，缺省值与 libp2p 相同 */
+export interface RelayServerConfig {
+  /** 同时保有的预约总数 */
+  maxReservations?: number;
+  /** 单个节点经不同连接保有的预约数 */
+  maxReservationsPerPeer?: number;
+  /** 预约有效期（秒），客户端会在到期前自动续约 */
+  reservationDurationSecs?: number;
+  /** 同时中继的连接总数 */
+  maxCircuits?: number;
+  maxCircuitsPerPeer?: number;
+  /** 单条中继连接的时长上限（秒），超过后服务端关闭连接 */
+  maxCircuitDurationSecs?: number;
+  /** 单条中继连接每个方向可转发的字节数 */
+  maxCircuitBytes?: number;
+}
+
+/** 中继服务端告知的连接限制 */
+export interface CircuitLimit {
+  durationSecs: number | null;
+  dataBytes: number | null;
+}
+
+/**
+ * 中继服务端与客户端事件
+ *
+ * 通过中继监听时用 `/p2p/<中继>/p2p-circuit` 调用 listenOn，已连接中继时可省略中继地址；
+ * 经中继拨号其他节点用 `<中继地址>/p2p/<中继>/p2p-circuit/p2p/<目标>`
+ */
+export type RelayEvent =
+  /** 服务端接受了预约，renewed 表示续约 */
+  | { kind: "reservationAccepted"; peerId: string; renewed: boolean }
+  /** 服务端拒绝预约，通常是超过了预约上限或速率限制 */
+  | { kind: "reservationDenied"; peerId: string; status: string }
+  | { kind: "reservationClosed"; peerId: string }
+  | { kind: "reservationTimedOut"; peerId: string }
+  | { kind: "circuitOpened"; srcPeerId: string; dstPeerId: string }
+  | { kind: "circuitDenied"; srcPeerId: string; dstPeerId: string; status: string }
+  /** 中继连接关闭，超过时长或流量上限时带有错误 */
+  | { kind: "circuitClosed"; srcPeerId: string; dstPeerId: string; error: string | null }
+  /** 客户端在中继上的预约成功，之后可以通过中继地址被拨号 */
+  | { kind: "reserved"; relayPeerId: string; renewal: boolean; limit: CircuitLimit | null }
+  /** 客户端预约失败，对应的中继监听器随之关闭 */
+  | { kind: "reservationFailed"; relayPeerId: string; error: string }
+  /** 经中继拨出的连接已建立 */
+  | { kind: "outboundCircuit"; relayPeerId: string; limit: CircuitLimit | null }
+  /** 其他节点经中继拨入 */
+  | { kind: "inboundCircuit"; srcPeerId: string; limit: CircuitLimit | null };
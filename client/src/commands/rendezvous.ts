@@ -0,0 +1,54 @@
+export interface Registration {
+  peerId: string;
+  namespace: string;
+  addrs: string[];
+  /** 剩余有效期（秒） */
+  ttl: number;
+}
+
+/** rendezvous 客户端与服务端事件 */
+export type RendezvousEvent =
+  | { kind: "registered"; rendezvousNode: string; namespace: string; ttl: number }
+  | {
+      kind: "registerFailed";
+      rendezvousNode: string;
+      namespace: string;
+      error: string;
+    }
+  | {
+      kind: "discovered";
+      rendezvousNode: string;
+      registrations: Registration[];
+      /** 下次发现时带上，只返回新增的注册 */
+      cookie: string;
+    }
+  | {
+      kind: "discoverFailed";
+      rendezvousNode: string;
+      namespace: string | null;
+      error: string;
+    }
+  | { kind: "expired"; peerId: string }
+  | { kind: "peerRegistered"; registration: Registration }
+  | { kind: "peerNotRegistered"; peerId: string; namespace: string; error: string }
+  | { kind: "peerUnregistered"; peerId: string; namespace: string }
+  | { kind: "registrationExpired"; registration: Registration }
+  | { kind: "discoverServed"; peerId: string; count: number }
+  | { kind: "discoverNotServed"; peerId: string; error: string };
+
+export type RendezvousCommand =
+  | {
+      command: "rendezvousRegister";
+      rendezvousNode: string;
+      namespace: string;
+      /** 秒，缺省由服务端决定 */
+      ttl?: number;
+    }
+  | {
+      command: "rendezvousDiscover";
+      rendezvousNode: string;
+      namespace?: string;
+      limit?: number;
+      cookie?: string;
+    }
+  | { command: "rendezvousUnregister"; rendezvousNode: string; namespace: string };
@@ -0,0 +1,65 @@
+import { invoke } from "@tauri-apps/api/core";
+
+/** 收到请求后的自动应答方式 */
+export type Responder =
+  | { kind: "echo" }
+  /** 把请求中的字符串（含嵌套的）转为大写 */
+  | { kind: "uppercase" }
+  /** 等待 ms 毫秒后原样返回，用于观察超时 */
+  | { kind: "delay"; ms: number }
+  | { kind: "error"; message: string };
+
+/** 请求-响应协议参数，每个节点只运行一个协议 */
+export interface RpcConfig {
+  /** 必须以 / 开头，默认 /swarmbook/echo/1.0.0 */
+  protocol?: string;
+  /** 线上编码，两端必须一致 */
+  format?: "json" | "cbor";
+  /** 请求超时上限（秒），默认 10 */
+  timeoutSecs?: number;
+  responder?: Responder;
+}
+
+export type RpcResponse =
+  | { status: "ok"; body: unknown }
+  | { status: "error"; message: string };
+
+export interface RpcReply {
+  peerId: string;
+  requestId: string;
+  response: RpcResponse;
+  rttMs: number;
+}
+
+/** 请求-响应事件 */
+export type RpcEvent =
+  | {
+      kind: "requestReceived";
+      peerId: string;
+      requestId: string;
+      request: unknown;
+      responder: Responder;
+    }
+  | { kind: "responseSent"; peerId: string; requestId: string }
+  | { kind: "inboundFailed"; peerId: string; requestId: string; error: string }
+  /** 调用方超时放弃后才到达的响应也会推送 */
+  | {
+      kind: "responseReceived";
+      peerId: string;
+      requestId: string;
+      response: RpcResponse;
+      rttMs: number;
+    }
+  | { kind: "outboundFailed"; peerId: string; requestId: string; error: string };
+
+export type RpcCommand = { command: "setRpcResponder"; responder: Responder };
+
+/** 发送请求并等待响应，`timeoutMs` 只能比 RpcConfig.timeoutSecs 更短 */
+export function rpcRequest(
+  peerId: string,
+  request: unknown,
+  timeoutMs?: number,
+  nodeId?: string
+) {
+  return invoke<RpcReply>("rpc_request", { nodeId, peerId, request, timeoutMs });
+}
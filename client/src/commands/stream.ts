@@ -0,0 +1,83 @@
+import { invoke } from "@tauri-apps/api/core";
+
+/** 流上的收发方式，缺省为不分帧的 UTF-8 文本 */
+export interface StreamOptions {
+  /** lengthPrefixed 使用 unsigned varint 长度前缀，单帧不超过 1 MiB */
+  framing?: "raw" | "lengthPrefixed";
+  /** 写入时按此解码，收到的数据按此编码 */
+  encoding?: "utf8" | "hex" | "base64";
+}
+
+/** 入站流的处理方式 */
+export type StreamHandler =
+  /** 交给前端读写，与主动打开的流相同 */
+  | { kind: "manual" }
+  /** 把收到的每一帧原样写回 */
+  | { kind: "echo" };
+
+export interface StreamInfo {
+  streamId: number;
+  peerId: string;
+  protocol: string;
+  direction: "outbound" | "inbound";
+  options: Required<StreamOptions>;
+  handler: StreamHandler | null;
+}
+
+export interface AcceptHandler {
+  protocol: string;
+  handler: StreamHandler;
+  options: Required<StreamOptions>;
+}
+
+export interface StreamsSnapshot {
+  streams: StreamInfo[];
+  handlers: AcceptHandler[];
+}
+
+/** 原始流事件 */
+export type StreamEvent =
+  | { kind: "opened"; stream: StreamInfo }
+  /** len 为原始字节数 */
+  | { kind: "data"; streamId: number; data: string; len: number }
+  /** 对方关闭了写端，本地仍可继续写 */
+  | { kind: "remoteClosed"; streamId: number }
+  /** error 为 null 表示双方都已正常关闭 */
+  | { kind: "closed"; streamId: number; error: string | null };
+
+/** 在任意协议上打开原始流，未连接时会先拨号 */
+export function openStream(
+  peerId: string,
+  protocol: string,
+  options?: StreamOptions,
+  nodeId?: string
+) {
+  return invoke<StreamInfo>("open_stream", { nodeId, peerId, protocol, options });
+}
+
+/** 写入一条数据，返回写入的原始字节数 */
+export function writeStream(streamId: number, data: string, nodeId?: string) {
+  return invoke<number>("write_stream", { nodeId, streamId, data });
+}
+
+/** 关闭写端，之后仍会收到对方的数据直到对方也关闭 */
+export function closeStream(streamId: number, nodeId?: string) {
+  return invoke("close_stream", { nodeId, streamId });
+}
+
+export function acceptStreams(
+  protocol: string,
+  handler: StreamHandler,
+  options?: StreamOptions,
+  nodeId?: string
+) {
+  return invoke("accept_streams", { nodeId, protocol, handler, options });
+}
+
+export function stopAcceptingStreams(protocol: string, nodeId?: string) {
+  return invoke("stop_accepting_streams", { nodeId, protocol });
+}
+
+export function listStreams(nodeId?: string) {
+  return invoke<StreamsSnapshot>("list_streams", { nodeId });
+}
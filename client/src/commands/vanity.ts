@@ -0,0 +1,46 @@
+import { invoke, Channel } from "@tauri-apps/api/core";
+import type { ExportedKeypair, KeyFormat, KeyType } from "./peer-id";
+
+export interface VanityOptions {
+  keyType: KeyType;
+  /** 紧跟在固定头部（如 ed25519 的 12D3KooW）之后的前缀 */
+  prefix?: string;
+  suffix?: string;
+  caseInsensitive?: boolean;
+  /** 找到后导出密钥的格式，默认 protobuf */
+  format?: KeyFormat;
+}
+
+export type VanityEvent =
+  | { type: "started"; workers: number; expectedAttempts: number }
+  | {
+      type: "progress";
+      attempts: number;
+      attemptsPerSec: number;
+      /** 还没有尝试时为 null */
+      estimatedSecs: number | null;
+    }
+  | {
+      type: "found";
+      peerId: string;
+      /** 包含私钥 */
+      keypair: ExportedKeypair;
+      attempts: number;
+      elapsedMs: number;
+    }
+  | { type: "cancelled"; attempts: number }
+  | { type: "error"; message: string };
+
+/** 在所有 CPU 核心上搜索靓号 PeerId，重新开始会取消上一次搜索 */
+export function startVanitySearch(
+  options: VanityOptions,
+  onEvent: (event: VanityEvent) => void
+) {
+  const channel = new Channel<VanityEvent>();
+  channel.onmessage = onEvent;
+  return invoke("start_vanity_search", { options, onEvent: channel });
+}
+
+export function cancelVanitySearch() {
+  return invoke("cancel_vanity_search");
+}
@@ -25,6 +25,7 @@ async fn main() -> Result<()> {
             noise::Config::new,
             yamux::Config::default,
         )?
+        .with_quic()
         .with_behaviour(|keypair| MyBehaviour {
             ping: ping::Behaviour::default(),
             identify: identify::Behaviour::new(
@@ -38,6 +39,7 @@ async fn main() -> Result<()> {
         .build();
 
     swarm.listen_on("/ip4/0.0.0.0/tcp/0".parse()?)?;
+    swarm.listen_on("/ip4/0.0.0.0/udp/0/quic-v1".parse()?)?;
 
     if let Some(addr) = env::args().nth(1) {
         let remote: Multiaddr = addr.parse()?;
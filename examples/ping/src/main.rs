@@ -18,11 +18,13 @@ async fn main() -> Result<()> {
             noise::Config::new,
             yamux::Config::default,
         )?
+        .with_quic()
         .with_behaviour(|_keypair| ping::Behaviour::default())?
         .with_swarm_config(|cfg| cfg.with_idle_connection_timeout(Duration::from_secs(u64::MAX)))
         .build();
 
     swarm.listen_on("/ip4/0.0.0.0/tcp/0".parse()?)?;
+    swarm.listen_on("/ip4/0.0.0.0/udp/0/quic-v1".parse()?)?;
 
     if let Some(addr) = env::args().nth(1) {
         let remote: Multiaddr = addr.parse()?;